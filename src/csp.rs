@@ -0,0 +1,60 @@
+/// The CSP hash of a style sheet defined with one of the macros, registered when the program starts
+#[cfg(feature = "csp")]
+#[doc(hidden)]
+pub struct RegisteredCspHash(pub &'static str);
+
+#[cfg(feature = "csp")]
+inventory::collect!(RegisteredCspHash);
+
+/// Builds a Content-Security-Policy `style-src` directive allowing the `<style>` elements of all style sheets defined with the macros
+///
+/// With the `csp` feature enabled, every `style_sheet`, `inline_style_sheet`, `style_sheet_values` and `inline_style_sheet_values` in the program registers its `STYLE_SHEET_CSP_HASH`, including the ones in dependency crates. The hashes are sorted and only listed once.
+///
+/// **Usage:**
+///
+/// ```rust,ignore
+/// mod button {
+///     turf::style_sheet!("src/button.scss");
+/// }
+///
+/// let directive = turf::style_src_directive();
+/// // "style-src 'sha256-…' 'sha256-…'"
+/// ```
+#[cfg(feature = "csp")]
+pub fn style_src_directive() -> String {
+    let csp_hashes = inventory::iter::<RegisteredCspHash>
+        .into_iter()
+        .map(|registered_csp_hash| registered_csp_hash.0)
+        .collect::<Vec<_>>();
+
+    style_src_directive_for(&csp_hashes)
+}
+
+/// Builds a Content-Security-Policy `style-src` directive allowing the `<style>` elements of the given style sheets
+///
+/// Useful for policies of pages embedding only some of the style sheets, or for the `STYLE_SHEET_CSP_HASH` statics generated by the build script API, which are not registered. The hashes are sorted and only listed once.
+///
+/// **Usage:**
+///
+/// ```rust,ignore
+/// mod button {
+///     turf::style_sheet!("src/button.scss");
+/// }
+/// mod card {
+///     turf::style_sheet!("src/card.scss");
+/// }
+///
+/// let directive = turf::style_src_directive_for(&[button::STYLE_SHEET_CSP_HASH, card::STYLE_SHEET_CSP_HASH]);
+/// // "style-src 'sha256-…' 'sha256-…'"
+/// ```
+pub fn style_src_directive_for(csp_hashes: &[&str]) -> String {
+    let csp_hashes = csp_hashes.iter().collect::<std::collections::BTreeSet<_>>();
+    let mut directive = String::from("style-src");
+
+    for csp_hash in csp_hashes {
+        directive.push(' ');
+        directive.push_str(csp_hash);
+    }
+
+    directive
+}
@@ -0,0 +1,271 @@
+//! Recompilation of style sheets at runtime during development
+//!
+//! With the `hot-reload` feature enabled, the `STYLE_SHEET` generated by the macros is a [`HotStyleSheet`] instead of a `&'static str`.
+//! In debug builds, [`HotStyleSheet::current`] recompiles the style sheet whenever the SCSS file, one of its imports, one of the files in the `load_paths` or the `Cargo.toml` changed since the last call.
+//! In release builds, it always returns the CSS that was compiled together with the binary, and the paths of the source files are not embedded.
+
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime},
+};
+
+use turf_internals::{Compiler, Settings, StyleSheetKind, StyleSheetOptions};
+
+/// The minimum time between two checks for modified files
+pub(crate) const CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The source a [`HotStyleSheet`] was compiled from
+#[derive(Debug, Clone, Copy)]
+pub enum HotStyleSheetSource {
+    File(&'static str),
+    Inline(&'static str),
+}
+
+/// The files are only listed again after a change, the checks in between only read the modification times of the listed files
+#[derive(Debug)]
+struct HotReloadState {
+    css: Option<String>,
+    dependencies: Vec<PathBuf>,
+    load_path_errors: Vec<String>,
+    modification_times: HashMap<PathBuf, Option<SystemTime>>,
+    last_check: Instant,
+}
+
+/// A style sheet that is recompiled at runtime when its source files change
+///
+/// The struct dereferences to the CSS that was embedded at compile time. Use [`HotStyleSheet::current`] to obtain the latest CSS.
+/// The class names of a recompiled style sheet stay pinned to the names generated at compile time, so the `ClassName` constants keep matching the CSS. Classes added after compilation receive newly generated names.
+#[derive(Debug)]
+pub struct HotStyleSheet {
+    css: &'static str,
+    hash: &'static str,
+    source: HotStyleSheetSource,
+    manifest_dir: &'static str,
+    class_names: &'static [(&'static str, &'static str)],
+    dependencies: &'static [&'static str],
+    syntax: Option<&'static str>,
+    state: Mutex<Option<HotReloadState>>,
+}
+
+impl HotStyleSheet {
+    #[doc(hidden)]
+    pub const fn new(
+        css: &'static str,
+        hash: &'static str,
+        source: HotStyleSheetSource,
+        manifest_dir: &'static str,
+        class_names: &'static [(&'static str, &'static str)],
+        dependencies: &'static [&'static str],
+        syntax: Option<&'static str>,
+    ) -> Self {
+        Self {
+            css,
+            hash,
+            source,
+            manifest_dir,
+            class_names,
+            dependencies,
+            syntax,
+            state: Mutex::new(None),
+        }
+    }
+
+    /// The CSS that was embedded at compile time
+    pub fn embedded(&self) -> &'static str {
+        self.css
+    }
+
+    /// The hash of the style sheet at compile time, which identifies the style sheet during live reloading
+    pub fn hash(&self) -> &'static str {
+        self.hash
+    }
+
+    /// The source the style sheet was compiled from
+    pub fn source(&self) -> HotStyleSheetSource {
+        self.source
+    }
+
+    /// Returns the latest CSS of the style sheet
+    ///
+    /// In debug builds, the style sheet is recompiled if its source files were modified. If the recompilation fails, the error is printed and the previous CSS is returned.
+    pub fn current(&self) -> Cow<'static, str> {
+        if !cfg!(debug_assertions) {
+            return Cow::Borrowed(self.css);
+        }
+
+        let mut state = match self.state.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        let state = match *state {
+            Some(ref mut state) => state,
+            None => {
+                let dependencies = self
+                    .dependencies
+                    .iter()
+                    .map(PathBuf::from)
+                    .collect::<Vec<_>>();
+
+                let mut load_path_errors = Vec::new();
+
+                return match self.settings().and_then(|settings| {
+                    self.watched_files(&settings, &dependencies, &mut load_path_errors)
+                }) {
+                    Ok(watched_files) => {
+                        *state = Some(HotReloadState {
+                            css: None,
+                            dependencies,
+                            load_path_errors,
+                            modification_times: modification_times(watched_files),
+                            last_check: Instant::now(),
+                        });
+                        Cow::Borrowed(self.css)
+                    }
+                    Err(error) => {
+                        hot_reload_message(&error);
+                        Cow::Borrowed(self.css)
+                    }
+                };
+            }
+        };
+
+        if state.last_check.elapsed() >= CHECK_INTERVAL {
+            state.last_check = Instant::now();
+
+            let modified = state
+                .modification_times
+                .iter()
+                .any(|(path, recorded_time)| modification_time(path) != *recorded_time);
+            if modified {
+                // the settings and the listed files could have changed as well
+                let watched_files = self.settings().and_then(|settings| {
+                    match self.recompile(&settings) {
+                        Ok((css, dependencies)) => {
+                            state.css = Some(css);
+                            state.dependencies = dependencies;
+                        }
+                        Err(error) => hot_reload_message(&error),
+                    }
+
+                    self.watched_files(&settings, &state.dependencies, &mut state.load_path_errors)
+                });
+
+                match watched_files {
+                    Ok(watched_files) => {
+                        state.modification_times = modification_times(watched_files)
+                    }
+                    Err(error) => hot_reload_message(&error),
+                }
+            }
+        }
+
+        match state.css {
+            Some(ref css) => Cow::Owned(css.clone()),
+            None => Cow::Borrowed(self.css),
+        }
+    }
+
+    fn settings(&self) -> Result<Settings, turf_internals::Error> {
+        Ok(Settings::from_manifest_dir(Path::new(self.manifest_dir))?)
+    }
+
+    /// The style sheet, its dependencies, the files in the `load_paths` and the `Cargo.toml` containing the settings
+    fn watched_files(
+        &self,
+        settings: &Settings,
+        dependencies: &[PathBuf],
+        load_path_errors: &mut Vec<String>,
+    ) -> Result<Vec<PathBuf>, turf_internals::Error> {
+        let walked_files = settings.load_path_files(Path::new(self.manifest_dir))?;
+
+        // unreadable load path entries are only reported when they change, not on every check
+        let errors = walked_files
+            .errors
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        if errors != *load_path_errors {
+            for error in errors
+                .iter()
+                .filter(|error| !load_path_errors.contains(error))
+            {
+                eprintln!("🌱 turf [WARN]: skipped load path entry - {error}");
+            }
+            *load_path_errors = errors;
+        }
+
+        let mut watched_files = walked_files.files;
+        watched_files.extend(dependencies.iter().cloned());
+        watched_files.push(Path::new(self.manifest_dir).join("Cargo.toml"));
+
+        if let HotStyleSheetSource::File(path) = self.source {
+            watched_files.push(PathBuf::from(path));
+        }
+
+        Ok(watched_files)
+    }
+
+    fn recompile(
+        &self,
+        settings: &Settings,
+    ) -> Result<(String, Vec<PathBuf>), turf_internals::Error> {
+        let style_sheet = match self.source {
+            HotStyleSheetSource::File(path) => StyleSheetKind::File(PathBuf::from(path)),
+            HotStyleSheetSource::Inline(style_sheet) => {
+                StyleSheetKind::Inline(String::from(style_sheet))
+            }
+        };
+
+        let options = StyleSheetOptions {
+            syntax: self.syntax.and_then(|syntax| syntax.parse().ok()),
+            ..Default::default()
+        };
+
+        let compiled_style_sheet = Compiler::new(settings.clone(), self.manifest_dir)
+            .with_class_names(self.class_names.iter().copied())
+            .compile_with_options(style_sheet, &options)?;
+
+        Ok((
+            compiled_style_sheet.css_with_inline_source_map(),
+            compiled_style_sheet.dependencies,
+        ))
+    }
+}
+
+impl std::ops::Deref for HotStyleSheet {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        self.css
+    }
+}
+
+impl std::fmt::Display for HotStyleSheet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.current())
+    }
+}
+
+fn modification_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+fn modification_times(watched_files: Vec<PathBuf>) -> HashMap<PathBuf, Option<SystemTime>> {
+    watched_files
+        .into_iter()
+        .map(|path| {
+            let modification_time = modification_time(&path);
+            (path, modification_time)
+        })
+        .collect()
+}
+
+fn hot_reload_message(error: &turf_internals::Error) {
+    eprintln!("🌱 turf [ERROR]: hot reload failed - {error}");
+}
@@ -77,19 +77,39 @@
 //! [package.metadata.turf]
 //! minify = true
 //! load_paths = ["path/to/shared/scss/files", "path/to/other/shared/scss/files"]
+//! package_dirs = ["vendor"]
+//! global_sources = ["vendor/**"]
+//! bundle_css_imports = true
+//! source_maps = false
+//! drafts = ["custom_media"]
 //! browser_targets = [
 //!     "defaults",
 //!     "> 5%",
 //!     "safari 12",
 //! ]
 //!
+//! [package.metadata.turf.features]
+//! include = ["nesting"]
+//! exclude = ["light_dark"]
+//!
 //! [package.metadata.turf.class_names]
 //! template = "<original_name>-with-custom-<id>"
 //! excludes = ["exclude-this-class-please", "^abc-[123]{4}"]
 //!
 //! [package.metadata.turf.file_output]
 //! global_css_file_path = "path/to/global.css"
+//! bundles = { admin = "path/to/admin.css" }
+//! order = [{ glob = "src/styles/reset.scss", priority = -10 }]
+//! media_split = { print = "print" }
 //! separate_css_files_path = "dir/for/separate/css/"
+//! separate_css_file_names = "mirrored"
+//! assets_path = "dir/for/assets/"
+//! compress = ["gzip", "brotli"]
+//! embed_compressed = false
+//!
+//! [package.metadata.turf.assets]
+//! inline_limit = 4096
+//! url_prefix = "/assets/"
 //! ```
 //!
 //! The following configuration options are available:
@@ -98,13 +118,31 @@
 //!
 //! - `load_paths`: Specifies additional paths to search for SCSS files to include during compilation. It accepts a list of string values, where each value represents a directory path to be included. This option allows you to import SCSS files from multiple directories.
 //!
+//! - `package_dirs`: Specifies directories containing packages laid out like a `node_modules` directory, for example vendored CSS frameworks. A package is imported with a `~` in front of its name, e.g. `@use "~bootstrap/scss/grid"`. Importing the package itself, e.g. `@use "~bootstrap" as bootstrap`, loads the file specified in the `sass` or `style` field of its `package.json`. Packages are only looked up on disk.
+//!
 //! - `browser_targets`: Defines the target browser versions for compatibility when generating CSS. It accepts an array of strings in [browserslist](https://browsersl.ist/) format (e.g., "defaults", "> 5%", "safari 12"). This ensures the generated CSS is compatible with the specified browser versions.
 //!
+//! - `drafts`: An array of draft syntaxes lightningcss parses in addition to standard CSS. The available drafts are `"custom_media"` for `@custom-media` rules, `"nesting"` and `"deep_selector_combinator"` for the `>>>` and `/deep/` combinators. Custom media queries are always resolved, as no browser supports them. lightningcss has no support for custom selectors.
+//!
+//! - `features`: Forces features to be compiled or left as they are, regardless of the `browser_targets`. It expects a structure that contains two arrays of features to include and to exclude.
+//!
 //! - `class_names`: Allows configuration of the CSS class name generation. It expects a structure that contains two values for generating CSS class names and excluding class names from the uniquification process.
 //!
+//! - `global_sources`: An array of glob patterns for files whose classes are kept global, e.g. `["vendor/**"]`. Classes of rules generated by a matching file keep their original names, so third-party libraries keep working, while the classes of your own rules are still uniquified, even if they share a name with a class of the library. Patterns are matched against paths relative to the directory of the `Cargo.toml`. Files outside of that directory are matched by their absolute path, e.g. with `"**/node_modules/**"`.
+//!
+//! - `bundle_css_imports` (default: `false`): Inlines the local CSS files imported with `@import`, e.g. `@import "reset.css" layer(base)`, instead of leaving the imports to the browser. Imports are resolved relative to the importing file and then against the `load_paths`. The `layer`, `supports` and media conditions of an import are kept by wrapping the imported rules in the corresponding blocks. Remote imports like `@import "https://fonts.example.com/font.css"` stay in place.
+//!
+//! - `source_maps` (default: `false`): Generates source maps of the compiled CSS for the browser devtools, see [Source Maps](#source-maps).
+//!
 //! - `debug` (default: `false`): When set to true, this option will enable debug output of the read configuration and the generated CSS class names. This can be helpful for troubleshooting and understanding how the CSS is being generated.
 //!
-//! - `file_output`: Enables output of compiled CSS. It expects a structure that contains two values for a single global CSS file or separate CSS files for each compiled SCSS file.
+//! - `file_output`: Enables output of compiled CSS. It expects a structure that contains values for a single global CSS file, separate CSS files for each compiled SCSS file and a directory for assets.
+//!
+//! - `assets`: Enables rewriting of the `url()` references to local files. It expects a structure that contains two values for inlining small files and for the URL of all other files.
+//!
+//! - `cache`: Configures the persistent compile cache. It expects a structure that contains two values for enabling the cache and for the cache directory.
+//!
+//! - `load_path_files`: Configures which files in the `load_paths` are watched for changes when hot reloading. It expects a structure that contains two values for the watched file extensions and for ignored files.
 //!
 //! #### The `class_names` Key
 //!
@@ -118,11 +156,59 @@
 //!
 //! - `excludes`: An array of regex patterns that exclude class names in your SCSS files from the class name uniquification process.
 //!
+//! #### The `features` Key
+//!
+//! Features are given by the snake case name of a [lightningcss feature](https://docs.rs/lightningcss/1.0.0-alpha.65/lightningcss/targets/struct.Features.html), e.g. `"nesting"`, `"light_dark"` or `"vendor_prefixes"`. The groups `"selectors"`, `"media_queries"` and `"colors"` contain all features of their kind.
+//!
+//! - `include`: Features that are always compiled into syntax supported by older browsers, even if all browser targets support them.
+//!
+//! - `exclude`: Features that are never compiled, even if a browser target doesn't support them.
+//!
 //! #### The `file_output` Key
 //!
-//! - `global_css_file_path`: Specifies the file path for a global CSS file. If set, a CSS file will be created at the provided path, and all compiled styles will be written to this file. This allows you to have a single CSS file containing all the compiled styles.
+//! The output of every style sheet is stored as a fragment in the Cargo target directory, and the output files are reassembled from all fragments whenever a style sheet is compiled. The output therefore stays complete when an incremental build only recompiles some of your crates, and the style sheets are always written in the same order, sorted by their priority and then by their path relative to the `Cargo.toml`. Crates writing to the same output paths share their fragments. The fragments of style sheets that were removed from a crate are dropped the next time the crate is compiled, and the fragments of deleted style sheet files whenever the output is reassembled. Inline style sheets of a crate that no longer defines any style sheets are only dropped by `cargo clean`. `cargo check`, `cargo doc` and rust-analyzer don't write any output files. Crates are told apart by the environment variables Cargo sets for rustc, and the kind of build is guessed from the rustc arguments, so builds that bypass Cargo or pass unusual arguments to rustc through a wrapper may write output files from `cargo check` runs or mix up the fragments of crates.
+//!
+//! - `global_css_file_path`: Specifies the file path for a global CSS file. If set, a CSS file will be created at the provided path, and all compiled styles will be written to this file. This allows you to have a single CSS file containing all the compiled styles. A `<hash>` in the file name is replaced with a hash of the CSS for cache busting, e.g. `app.<hash>.css` is written to `app.3f2a1b0c9d8e7f6a.css`, and the files written for an earlier CSS are removed. Since the final file name changes with every compiled style sheet, it can't be exposed to your code by the macros. A `turf-global-manifest.json` next to the global CSS file lists its final `file` name, its `size`, its Subresource Integrity hash as `integrity` and the `style_sheets` it contains. Bundles in the same directory are listed in the same manifest together with their `bundle` name. The build script API exposes the file name of its bundle as well.
+//!
+//! - `bundles`: Maps bundle names to the file paths of their CSS files, e.g. `{ admin = "dist/admin.css", public = "dist/public.css" }`, to split the CSS by the pages it is used on. A style sheet is assigned to a bundle with the `bundle` argument of the macros, e.g. `turf::style_sheet!("admin.scss", bundle = "admin")`, and its CSS is written to the file of the bundle instead of the global CSS file. The global CSS file contains all style sheets without a bundle. The file paths support the `<hash>` placeholder like the `global_css_file_path`.
+//!
+//! - `order`: An array of glob patterns with a priority, e.g. `[{ glob = "src/styles/reset.scss", priority = -20 }, { glob = "src/styles/base/**", priority = -10 }]`. Style sheets with a lower priority come first in the global CSS file and the bundles, so resets and base styles can precede component styles regardless of their paths. The patterns are matched against the path of a style sheet relative to the `Cargo.toml`, and the first matching pattern determines its priority. Style sheets without a matching pattern have the priority `0`. The `priority` argument of the macros, e.g. `turf::style_sheet!("reset.scss", priority = -20)`, takes precedence over the patterns.
+//!
+//! - `media_split`: Maps names to media queries, e.g. `{ print = "print", wide = "(min-width: 1200px)" }`. The top-level `@media` blocks matching one of the queries are moved out of the global CSS file and the bundles into a file for each media, named after the file with the name of the media in front of its extension, e.g. `global.print.css`. The rules inside of the blocks can then be loaded with the `media` attribute of a `<link>`. The files are listed in the `turf-global-manifest.json` together with their `media` query. `@media` blocks nested inside of other rules and the separate CSS files are not split.
+//!
+//! - `separate_css_files_path`: Specifies the directory path for separate CSS files. If set, all compiled CSS files will be saved in the specified directory. Each compiled SCSS file will have its corresponding CSS file in this directory, allowing for modular CSS management. The directory also contains a `turf-manifest.json`, which maps the path of each style sheet relative to the `Cargo.toml` to its CSS file, the hash and size of the CSS, its Subresource Integrity hash, its bundle and its class names, so servers and bundlers can look up the file of a style sheet. Inline style sheets are listed as `inline:<hash>`.
+//!
+//! - `separate_css_file_names` (default: `"mirrored"`): Specifies how the separate CSS files are named. With `"mirrored"`, the directory structure of the style sheets relative to the `Cargo.toml` is mirrored, e.g. `src/button/style.scss` is written to `src/button/style.css`. Style sheets outside of that directory are named like with `"hashed"`. The file name for inline SCSS style definitions will be a 64 bit hash that is computed from the original SCSS style. Style sheets mirrored to the same file, e.g. `style.scss` and `style.sass` in the same directory, or style sheets of the same path in different packages writing to the same directory, are reported as an error. With `"hashed"`, each file is named after its style sheet and a hash of its CSS, e.g. `style.3f2a1b0c9d8e7f6a.css`, and inline style sheets after the hash alone.
+//!
+//! - `assets_path`: Specifies the directory the assets referenced with `url()` are copied into, see the `assets` key.
+//!
+//! - `compress`: An array of the formats `"gzip"` and `"brotli"`. Every CSS file written by the file output is accompanied by a sibling compressed with each format, e.g. `global.css.gz` and `global.css.br`, for static file servers that serve pre-compressed files. Siblings are only compressed again when the CSS changed.
+//!
+//! - `embed_compressed` (default: `false`): If set to true, the `style_sheet!` and `inline_style_sheet!` macros additionally emit the CSS compressed with each of the `compress` formats as `STYLE_SHEET_GZIP` and `STYLE_SHEET_BROTLI` statics of the type `&[u8]`, for servers that serve the embedded CSS directly. The `style_sheet_values!` and `inline_style_sheet_values!` macros then return a third tuple element with a `gzip` and `brotli` field of the same type for each format, e.g. `let (style_sheet, class_names, compressed) = turf::style_sheet_values!("src/button.scss");`. The compressed statics always contain the CSS of the compilation, even with hot reloading.
+//!
+//! #### The `assets` Key
+//!
+//! If the `assets` key is present, every `url()` pointing to a local file, e.g. `url(../img/logo.svg)`, is resolved relative to the style sheet file and then against the `load_paths`. Inline style sheets resolve URLs relative to the directory of the `Cargo.toml`. A referenced file that does not exist is reported as error. Absolute URLs, e.g. `url(/favicon.ico)`, remote URLs and data URIs are left untouched. Referenced files are tracked like imported style sheets.
+//!
+//! - `inline_limit` (default: `0`): Files smaller than this number of bytes are inlined as base64 data URIs. URLs with a fragment, e.g. `url(icons.svg#home)`, are never inlined.
+//!
+//! - `url_prefix` (default: `""`): All other files are renamed to a content-hashed file name, e.g. `logo.3f2a1b0c9d8e7f6a.svg`, which is appended to this prefix. The files are copied to the `assets_path` of the `file_output`, the build script API copies them into an `assets` directory next to the CSS bundle.
+//!
+//! #### The `cache` Key
+//!
+//! Compiled style sheets are cached on disk, so unchanged style sheets are not recompiled on every build. A cached style sheet is reused as long as the style sheet, every file it imported and the settings stay unchanged, and no file was created where grass looked for one of its imports, e.g. a new partial next to the style sheet shadowing a partial of the `load_paths`.
+//!
+//! - `enabled` (default: `true`): Specifies whether the compile cache is used.
+//!
+//! - `directory` (default: `"<target directory>/turf-cache"`): Specifies the directory the cache entries are stored in.
+//!
+//! #### The `load_path_files` Key
+//!
+//! The `load_paths` are walked recursively to find the files watched when hot reloading. Symbolic links are followed, but every directory is only visited once, and entries that cannot be read are reported and skipped. The key only affects hot reloading, the macros are rebuilt when one of the files a style sheet actually imports changes, regardless of the key.
+//!
+//! - `extensions` (default: `["scss", "sass", "css"]`): Specifies the file extensions of the watched files.
 //!
-//! - `separate_css_files_path`: Specifies the directory path for separate CSS files. If set, all compiled CSS files will be saved in the specified directory. Each compiled SCSS file will have its corresponding CSS file in this directory, allowing for modular CSS management. The file name for inline SCSS style definitions will be a 64 bit hash that is computed from the original SCSS style.
+//! - `ignore`: An array of `.gitignore`-style patterns that exclude files and directories from being watched. The patterns are matched against paths relative to the load path.
 //!
 //! ### Additional Macros
 //!
@@ -180,6 +266,191 @@
 //! };
 //! let some_class_name = class_names.some_class;
 //! ```
+//!
+//! ### Sass and CSS Files
+//!
+//! The syntax of a style sheet file is picked from its extension. Files ending in `.sass` are compiled with the indented Sass syntax, and `.css` files are not compiled at all, only their class names are uniquified and the CSS is optimized. All other files are compiled as SCSS.
+//!
+//! The syntax can be overridden with the `syntax` argument, which also allows writing inline style sheets in plain CSS:
+//!
+//! ```rust,ignore
+//! turf::style_sheet!("path/to/style.txt", syntax = "css");
+//!
+//! turf::inline_style_sheet! {
+//!     syntax = "css";
+//!
+//!     .some-class {
+//!         color: red;
+//!     }
+//! }
+//! ```
+//!
+//! ### Importing Style Sheets from Crates
+//!
+//! Style sheets can be imported from dependency crates with the `crate:` URL scheme. The crate name is followed by the path of the style sheet inside of the directories the crate exports.
+//!
+//! ```scss
+//! @use "crate:design_system/tokens";
+//! ```
+//!
+//! A crate exports its style sheets by declaring the directories in its `Cargo.toml`:
+//!
+//! ```toml
+//! [package.metadata.turf]
+//! exports = ["scss"]
+//! ```
+//!
+//! The dependencies are located without running Cargo: path dependencies through your `Cargo.toml`, and registry and git dependencies by the version or commit pinned in the `Cargo.lock` in the sources Cargo extracts to the Cargo home before building. Until the crate was built once, e.g. when rust-analyzer expands the macros of a freshly cloned project, the import fails with an error asking to build it. Dependencies from other sources, e.g. vendored sources or local registries, are not supported. Renamed dependencies are imported by the name used in your `Cargo.toml`. Imported files are tracked like any other file, and updating the dependency recompiles the style sheet.
+//!
+//! ### Build Script API
+//!
+//! Instead of compiling each style sheet in a macro invocation, the [turf_build](https://docs.rs/turf_build) crate allows compiling all style sheets of a directory from a `build.rs` build script. The resulting CSS bundle is written into `OUT_DIR` together with a Rust module that contains the `STYLE_SHEET` static and the `ClassName` struct of every compiled style sheet. The module of each style sheet is named after its path relative to the compiled directory. Partials (files with a leading underscore) are not compiled on their own.
+//!
+//! The settings are read from the `Cargo.toml` just like for the macros, except for the `file_output` key. Its bundles, separate files, compression, media split and assets path only apply to the macros, a build script reading a `file_output` key prints a warning.
+//!
+//! **Usage:**
+//!
+//! ```rust,ignore
+//! // build.rs
+//! fn main() {
+//!     turf_build::Builder::new()
+//!         .load_path("path/to/shared/scss/files")
+//!         // optional, defaults to `turf.css`
+//!         .css_file_name("turf.<hash>.css")
+//!         .compile_dir("src/styles")
+//!         .unwrap();
+//! }
+//! ```
+//!
+//! ```rust,ignore
+//! // src/main.rs
+//! mod styles {
+//!     include!(concat!(env!("OUT_DIR"), "/turf.rs"));
+//! }
+//!
+//! let css_bundle = styles::STYLE_SHEET;
+//! // the final file name of the bundle in `OUT_DIR`, e.g. `turf.3f2a1b0c9d8e7f6a.css`
+//! let css_bundle_file_name = styles::CSS_FILE_NAME;
+//! // the Subresource Integrity hash of the bundle, e.g. `sha384-…`
+//! let css_bundle_integrity = styles::CSS_FILE_INTEGRITY;
+//! // the items generated for `src/styles/button/primary.scss`
+//! let style_sheet = styles::button_primary::STYLE_SHEET;
+//! let some_class_name = styles::button_primary::ClassName::SOME_CLASS;
+//! ```
+//!
+//! ### Runtime Compilation
+//!
+//! With the `runtime` feature enabled, `turf::compile` compiles SCSS while your application is running. The style sheet passes through the same pipeline as the style sheets compiled by the macros, so identical settings produce identical class names. The settings are created with `turf::Settings::builder()` instead of being read from the `Cargo.toml`. The feature also compiles to `wasm32` targets.
+//!
+//! To let a style sheet compiled at runtime target the classes of a style sheet compiled by the macros, `turf::compile_with_class_names` reuses an existing mapping of original to generated class names.
+//!
+//! **Usage:**
+//!
+//! ```rust,ignore
+//! turf::style_sheet!("scss/file/path.scss");
+//!
+//! let settings = turf::Settings::builder()
+//!     .minify(true)
+//!     .browser_targets(["defaults"])
+//!     .build()?;
+//!
+//! let compiled_style_sheet = turf::compile_with_class_names(
+//!     &uploaded_scss,
+//!     &settings,
+//!     [("TopLevelClass", ClassName::TOP_LEVEL_CLASS)],
+//! )?;
+//!
+//! let css = compiled_style_sheet.css;
+//! ```
+//!
+//! ### Content Security Policy
+//!
+//! All four macros also generate a `STYLE_SHEET_CSP_HASH` static, the SHA-256 hash of the CSS in the format of a Content-Security-Policy source, e.g. `'sha256-…'`. A policy listing the hash allows a `<style>` element containing the `STYLE_SHEET`, even if inline styles are blocked otherwise. With the `csp` feature enabled, every macro registers its hash, and the `turf::style_src_directive` function builds the `style-src` directive from the hashes of all style sheets of the program, including the ones of dependency crates. The registration runs a small constructor per style sheet before `main`, using the [inventory](https://docs.rs/inventory) crate. On `wasm32` targets, this may require calling `__wasm_call_ctors`, see its documentation. `turf::style_src_directive_for` is always available and builds the directive from a list of hashes instead, e.g. for the `STYLE_SHEET_CSP_HASH` of each style sheet in the modules generated by the build script API, which are not registered. Hot reloaded CSS no longer matches the hash, so the policy has to allow inline styles during development.
+//!
+//! ```rust,ignore
+//! mod button {
+//!     turf::style_sheet!("src/button.scss");
+//! }
+//! mod card {
+//!     turf::style_sheet!("src/card.scss");
+//! }
+//!
+//! // "style-src 'sha256-…' 'sha256-…'", requires the `csp` feature
+//! let content_security_policy = turf::style_src_directive();
+//!
+//! // only the button
+//! let button_content_security_policy = turf::style_src_directive_for(&[button::STYLE_SHEET_CSP_HASH]);
+//! ```
+//!
+//! Every `</style` in the compiled CSS, which can only occur in strings and URLs, is escaped as `<\/style`. The CSS can therefore be embedded into a `<style>` element as is, without ending the element early.
+//!
+//! ### Subresource Integrity
+//!
+//! The CSS files written by the file output are listed with their Subresource Integrity hash in the manifests, see the `file_output` key, and the build script API exposes the hash of its bundle as `CSS_FILE_INTEGRITY`. The `turf::stylesheet_link` and `turf::preload_link` functions render the `<link>` tag of a CSS file from its URL and integrity hash. They don't look up the files of a bundle by its name, the file name and integrity hash of the global CSS file and of each bundle are read from the `turf-global-manifest.json` instead:
+//!
+//! ```rust,ignore
+//! let href = format!("/static/{}", styles::CSS_FILE_NAME);
+//!
+//! // <link rel="preload" as="style" href="/static/turf.3f2a1b0c9d8e7f6a.css" integrity="sha384-…" crossorigin="anonymous">
+//! let preload = turf::preload_link(&href, styles::CSS_FILE_INTEGRITY);
+//! // <link rel="stylesheet" href="/static/turf.3f2a1b0c9d8e7f6a.css" integrity="sha384-…" crossorigin="anonymous">
+//! let stylesheet = turf::stylesheet_link(&href, styles::CSS_FILE_INTEGRITY);
+//! ```
+//!
+//! ### Source Maps
+//!
+//! With `source_maps = true`, turf generates a source map for every compiled style sheet. Style sheets embedded as `STYLE_SHEET` end with a `sourceMappingURL` comment containing the map as a data URI, including the recompiled CSS of hot reloading. The file output writes a `.map` file next to the global CSS file, each bundle and each separate CSS file, e.g. `global.css.map`, and references it with a `sourceMappingURL` comment at the end of the CSS file. The style sheets of the global CSS file and the bundles then start on a new line each. The CSS bundle of the build script API gets a `.map` file as well, e.g. `turf.css.map`. With a `media_split`, the global CSS file, the bundles and their media files have no source maps.
+//!
+//! grass, the Sass compiler used by turf, doesn't produce source maps, so the maps point to the lines of the CSS compiled by grass, which is embedded in the map, instead of the lines of the original SCSS files. The sources are therefore named after the style sheet files relative to the `Cargo.toml` with a `.css` extension appended, e.g. `src/button.scss.css`, so the devtools show the compiled CSS of each style sheet under its own file. As source maps add the compiled CSS to every embedded style sheet, enable them in the development profile only:
+//!
+//! ```toml
+//! [package.metadata.turf-dev]
+//! source_maps = true
+//! ```
+//!
+//! For readable CSS in the devtools, also disable minification in the development profile, so the CSS is formatted with indentation and line breaks. A class name template containing `<original_name>` makes the rules of a class easy to find:
+//!
+//! ```toml
+//! [package.metadata.turf-dev]
+//! minify = false
+//!
+//! [package.metadata.turf-dev.class_names]
+//! template = "<original_name>-<id>"
+//! ```
+//!
+//! ### Hot Reloading
+//!
+//! With the `hot-reload` feature enabled, the `STYLE_SHEET` generated by the macros is a `turf::hot_reload::HotStyleSheet` instead of a `&'static str`. It dereferences to the CSS that was compiled together with your binary. In debug builds, `STYLE_SHEET.current()` checks whether the SCSS file, one of its imports, any watched file in the `load_paths` or the `Cargo.toml` changed and recompiles the style sheet at runtime, so style changes don't require rebuilding your application. The checks only compare the modification times of the known files, the settings are read and the `load_paths` are walked again after a change. The class names stay pinned to the names generated at compile time, so the `ClassName` constants keep matching the recompiled CSS. In release builds, `current()` always returns the embedded CSS, and the absolute paths of the source files needed for hot reloading are not embedded into the binary.
+//!
+//! The `*_values` macros return a `&'static HotStyleSheet` instead of a `&'static str` when the feature is enabled.
+//!
+//! **Usage:**
+//!
+//! ```rust,ignore
+//! turf::style_sheet!("scss/file/path.scss");
+//!
+//! let latest_css = STYLE_SHEET.current();
+//! ```
+//!
+//! #### Live Reloading
+//!
+//! The `live-reload` feature builds on hot reloading and pushes recompiled style sheets to the browser. `turf::live_reload::LiveReload` is a [tower](https://docs.rs/tower) service serving a server-sent events endpoint, and `LiveReload::client_script()` returns a small `<script>` element that connects to it. When a watched style sheet changes, the new CSS replaces the content of every `<style>` element whose `data-turf-style-sheet` attribute matches the style sheet's hash, without reloading the page. Both are only active in debug builds.
+//!
+//! **Usage with axum:**
+//!
+//! ```rust,ignore
+//! turf::style_sheet!("scss/file/path.scss");
+//!
+//! let live_reload = turf::live_reload::LiveReload::new("/turf-live-reload", [&STYLE_SHEET]);
+//! let client_script = live_reload.client_script();
+//!
+//! let app = axum::Router::new().route_service("/turf-live-reload", live_reload);
+//! ```
+//!
+//! ```html
+//! <style data-turf-style-sheet="{{ STYLE_SHEET.hash() }}">{{ STYLE_SHEET }}</style>
+//! {{ client_script }}
+//! ```
 
 /// Generates the static variable `STYLE_SHEET` and the `ClassName` struct with default settings or the settings specified in the `Cargo.toml`
 ///
@@ -250,3 +521,26 @@ pub use turf_macros::inline_style_sheet;
 /// let some_class_name = class_names.some_class;
 /// ```
 pub use turf_macros::inline_style_sheet_values;
+
+mod csp;
+#[cfg(feature = "csp")]
+pub use csp::style_src_directive;
+pub use csp::style_src_directive_for;
+mod links;
+pub use links::{preload_link, stylesheet_link};
+
+#[cfg(feature = "csp")]
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::csp::RegisteredCspHash;
+    pub use inventory;
+}
+
+#[cfg(feature = "hot-reload")]
+pub mod hot_reload;
+#[cfg(feature = "live-reload")]
+pub mod live_reload;
+#[cfg(feature = "runtime")]
+mod runtime;
+#[cfg(feature = "runtime")]
+pub use runtime::*;
@@ -0,0 +1,51 @@
+/// Renders a `<link rel="stylesheet">` tag for a CSS file, which the browser only applies if its content matches the integrity hash
+///
+/// The integrity hash is the Subresource Integrity hash written to the manifests of the file output or generated by the build script API, e.g. `CSS_FILE_INTEGRITY`. The URL and the hash are taken as they are, the CSS file of a bundle has to be looked up in the `turf-global-manifest.json`.
+///
+/// **Usage:**
+///
+/// ```rust
+/// let link = turf::stylesheet_link("/static/app.css", "sha384-abc");
+///
+/// assert_eq!(
+///     link,
+///     r#"<link rel="stylesheet" href="/static/app.css" integrity="sha384-abc" crossorigin="anonymous">"#
+/// );
+/// ```
+pub fn stylesheet_link(href: &str, integrity: &str) -> String {
+    format!(
+        r#"<link rel="stylesheet" href="{}" integrity="{}" crossorigin="anonymous">"#,
+        escape_attribute(href),
+        escape_attribute(integrity)
+    )
+}
+
+/// Renders a `<link rel="preload" as="style">` tag, which lets the browser fetch a CSS file early
+///
+/// The integrity hash has to match the one of the stylesheet link, otherwise the preloaded file is fetched again.
+///
+/// **Usage:**
+///
+/// ```rust
+/// let link = turf::preload_link("/static/app.css", "sha384-abc");
+///
+/// assert_eq!(
+///     link,
+///     r#"<link rel="preload" as="style" href="/static/app.css" integrity="sha384-abc" crossorigin="anonymous">"#
+/// );
+/// ```
+pub fn preload_link(href: &str, integrity: &str) -> String {
+    format!(
+        r#"<link rel="preload" as="style" href="{}" integrity="{}" crossorigin="anonymous">"#,
+        escape_attribute(href),
+        escape_attribute(integrity)
+    )
+}
+
+fn escape_attribute(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,277 @@
+//! Pushing recompiled style sheets to the browser during development
+//!
+//! [`LiveReload`] is a [tower](https://docs.rs/tower) service that serves a [server-sent events](https://developer.mozilla.org/en-US/docs/Web/API/Server-sent_events) endpoint.
+//! Whenever one of its [`HotStyleSheet`]s is recompiled, the new CSS is sent to all connected browsers. The script returned by [`LiveReload::client_script`] replaces the content of every `<style>` element whose `data-turf-style-sheet` attribute matches the [`HotStyleSheet::hash`], without reloading the page.
+//!
+//! Both the endpoint and the client script are only active in debug builds. In release builds, the service responds with `404 Not Found` and the client script is empty.
+
+use std::{
+    convert::Infallible,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, OnceLock, Weak},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use http::{header, Request, Response, StatusCode};
+use http_body::Frame;
+use tokio::sync::broadcast;
+
+use crate::hot_reload::{HotStyleSheet, CHECK_INTERVAL};
+
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+const EVENT_NAME: &str = "turf-style-sheet";
+const CLIENT_SCRIPT: &str = r#"(() => {
+    const source = new EventSource("<endpoint>");
+    source.addEventListener("turf-style-sheet", (event) => {
+        const separator = event.data.indexOf("\n");
+        const hash = event.data.slice(0, separator);
+        const css = event.data.slice(separator + 1);
+        document
+            .querySelectorAll(`style[data-turf-style-sheet="${hash}"]`)
+            .forEach((style) => (style.textContent = css));
+    });
+})();"#;
+
+struct LiveReloadInner {
+    endpoint: String,
+    style_sheets: Vec<&'static HotStyleSheet>,
+    sender: broadcast::Sender<Bytes>,
+    watcher: OnceLock<()>,
+}
+
+/// A tower service pushing recompiled style sheets to the browser
+///
+/// **Usage with axum:**
+///
+/// ```rust,ignore
+/// turf::style_sheet!("scss/file/path.scss");
+///
+/// let live_reload = turf::live_reload::LiveReload::new("/turf-live-reload", [&STYLE_SHEET]);
+/// let client_script = live_reload.client_script();
+///
+/// let app = axum::Router::new().route_service("/turf-live-reload", live_reload);
+/// ```
+///
+/// ```html
+/// <style data-turf-style-sheet="{{ STYLE_SHEET.hash() }}">{{ STYLE_SHEET }}</style>
+/// {{ client_script }}
+/// ```
+#[derive(Clone)]
+pub struct LiveReload {
+    inner: Arc<LiveReloadInner>,
+}
+
+impl LiveReload {
+    /// Creates the service for the watched style sheets
+    ///
+    /// `endpoint` is the path the service is mounted at, it is used by the client script to connect to the service.
+    pub fn new<S, I>(endpoint: S, style_sheets: I) -> Self
+    where
+        S: Into<String>,
+        I: IntoIterator<Item = &'static HotStyleSheet>,
+    {
+        let (sender, _) = broadcast::channel(16);
+
+        Self {
+            inner: Arc::new(LiveReloadInner {
+                endpoint: endpoint.into(),
+                style_sheets: style_sheets.into_iter().collect(),
+                sender,
+                watcher: OnceLock::new(),
+            }),
+        }
+    }
+
+    /// Returns a `<script>` element that connects to the service and swaps updated style sheets
+    pub fn client_script(&self) -> String {
+        if !cfg!(debug_assertions) {
+            return String::new();
+        }
+
+        let endpoint = self.inner.endpoint.replace('"', "%22");
+
+        format!(
+            "<script>{}</script>",
+            CLIENT_SCRIPT.replace("<endpoint>", &endpoint)
+        )
+    }
+
+    fn start_watcher(&self) {
+        self.inner.watcher.get_or_init(|| {
+            let inner = Arc::downgrade(&self.inner);
+            let style_sheets = self.inner.style_sheets.clone();
+
+            std::thread::spawn(move || watch_style_sheets(inner, style_sheets));
+        });
+    }
+}
+
+fn watch_style_sheets(inner: Weak<LiveReloadInner>, style_sheets: Vec<&'static HotStyleSheet>) {
+    let mut last_style_sheets = style_sheets
+        .iter()
+        .map(|style_sheet| style_sheet.current())
+        .collect::<Vec<_>>();
+    let mut last_keep_alive = Instant::now();
+
+    loop {
+        std::thread::sleep(CHECK_INTERVAL);
+
+        let Some(inner) = inner.upgrade() else {
+            break;
+        };
+
+        for (style_sheet, last_css) in style_sheets.iter().zip(last_style_sheets.iter_mut()) {
+            let css = style_sheet.current();
+
+            if css != *last_css {
+                let _ = inner
+                    .sender
+                    .send(encode_style_sheet_event(style_sheet.hash(), &css));
+                *last_css = css;
+            }
+        }
+
+        if last_keep_alive.elapsed() >= KEEP_ALIVE_INTERVAL {
+            last_keep_alive = Instant::now();
+            let _ = inner.sender.send(Bytes::from_static(b": keep-alive\n\n"));
+        }
+    }
+}
+
+fn encode_style_sheet_event(hash: &str, css: &str) -> Bytes {
+    let mut event = format!("event: {EVENT_NAME}\ndata: {hash}\n");
+
+    for line in css.lines() {
+        event.push_str("data: ");
+        event.push_str(line);
+        event.push('\n');
+    }
+    event.push('\n');
+
+    Bytes::from(event)
+}
+
+impl<B> tower_service::Service<Request<B>> for LiveReload {
+    type Response = Response<LiveReloadBody>;
+    type Error = Infallible;
+    type Future = std::future::Ready<Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _request: Request<B>) -> Self::Future {
+        if !cfg!(debug_assertions) {
+            let mut response = Response::new(LiveReloadBody::empty());
+            *response.status_mut() = StatusCode::NOT_FOUND;
+            return std::future::ready(Ok(response));
+        }
+
+        self.start_watcher();
+
+        let body = LiveReloadBody::new(self.inner.sender.subscribe());
+        let response = Response::builder()
+            .header(header::CONTENT_TYPE, "text/event-stream")
+            .header(header::CACHE_CONTROL, "no-cache")
+            .body(body)
+            .expect("live reload response is valid");
+
+        std::future::ready(Ok(response))
+    }
+}
+
+type Receive = Pin<
+    Box<
+        dyn Future<
+                Output = (
+                    broadcast::Receiver<Bytes>,
+                    Result<Bytes, broadcast::error::RecvError>,
+                ),
+            > + Send,
+    >,
+>;
+
+fn receive(mut receiver: broadcast::Receiver<Bytes>) -> Receive {
+    Box::pin(async move {
+        let result = receiver.recv().await;
+        (receiver, result)
+    })
+}
+
+/// The event stream returned by [`LiveReload`]
+pub struct LiveReloadBody {
+    initial: Option<Bytes>,
+    next: Option<Receive>,
+}
+
+impl LiveReloadBody {
+    fn new(receiver: broadcast::Receiver<Bytes>) -> Self {
+        Self {
+            initial: Some(Bytes::from_static(b"retry: 1000\n\n")),
+            next: Some(receive(receiver)),
+        }
+    }
+
+    fn empty() -> Self {
+        Self {
+            initial: None,
+            next: None,
+        }
+    }
+}
+
+impl http_body::Body for LiveReloadBody {
+    type Data = Bytes;
+    type Error = Infallible;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        if let Some(initial) = self.initial.take() {
+            return Poll::Ready(Some(Ok(Frame::data(initial))));
+        }
+
+        loop {
+            let Some(next) = self.next.as_mut() else {
+                return Poll::Ready(None);
+            };
+
+            match next.as_mut().poll(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready((receiver, Ok(event))) => {
+                    self.next = Some(receive(receiver));
+                    return Poll::Ready(Some(Ok(Frame::data(event))));
+                }
+                Poll::Ready((receiver, Err(broadcast::error::RecvError::Lagged(_)))) => {
+                    self.next = Some(receive(receiver));
+                }
+                Poll::Ready((_, Err(broadcast::error::RecvError::Closed))) => {
+                    self.next = None;
+                }
+            }
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.initial.is_none() && self.next.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::encode_style_sheet_event;
+
+    #[test]
+    fn encode_multi_line_style_sheet() {
+        let event = encode_style_sheet_event("abc", ".test {\n  color: red;\n}\n");
+
+        assert_eq!(
+            event,
+            "event: turf-style-sheet\ndata: abc\ndata: .test {\ndata:   color: red;\ndata: }\n\n"
+        );
+    }
+}
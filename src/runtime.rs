@@ -0,0 +1,52 @@
+use std::path::PathBuf;
+
+pub use turf_internals::{
+    CompiledStyleSheet, Error, Settings, SettingsBuilder, SettingsError, StyleSheetKind,
+};
+
+/// Compiles SCSS to CSS at runtime
+///
+/// The style sheet passes through the same pipeline as the style sheets compiled by the macros, so the same settings produce the same class names. Relative load paths are resolved against the current working directory.
+///
+/// **Usage:**
+///
+/// ```rust,ignore
+/// let settings = turf::Settings::builder().minify(false).build()?;
+/// let compiled_style_sheet = turf::compile(".TopLevelClass { color: red; }", &settings)?;
+///
+/// let css = compiled_style_sheet.css;
+/// let some_class_name = compiled_style_sheet.class_names.get("TopLevelClass");
+/// ```
+pub fn compile(scss: &str, settings: &Settings) -> Result<CompiledStyleSheet, Error> {
+    compile_with_class_names(scss, settings, std::iter::empty::<(String, String)>())
+}
+
+/// Compiles SCSS to CSS at runtime while reusing an existing class name mapping
+///
+/// Classes contained in `class_names` keep the given generated names, all other classes are renamed as usual. This allows style sheets compiled at runtime to target the classes of style sheets compiled by the macros.
+///
+/// **Usage:**
+///
+/// ```rust,ignore
+/// turf::style_sheet!("scss/file/path.scss");
+///
+/// let compiled_theme = turf::compile_with_class_names(
+///     ".TopLevelClass { color: blue; }",
+///     &turf::Settings::default(),
+///     [("TopLevelClass", ClassName::TOP_LEVEL_CLASS)],
+/// )?;
+/// ```
+pub fn compile_with_class_names<I, K, V>(
+    scss: &str,
+    settings: &Settings,
+    class_names: I,
+) -> Result<CompiledStyleSheet, Error>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: Into<String>,
+    V: Into<String>,
+{
+    turf_internals::Compiler::new(settings.clone(), PathBuf::new())
+        .with_class_names(class_names)
+        .compile(StyleSheetKind::Inline(String::from(scss)))
+}
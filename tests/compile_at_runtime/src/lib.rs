@@ -0,0 +1,80 @@
+#[test]
+fn compile_scss_at_runtime() {
+    let settings = turf::Settings::builder()
+        .minify(false)
+        .load_path("../variables")
+        .build()
+        .unwrap();
+
+    let compiled_style_sheet = turf::compile(
+        r#"
+            @import "color";
+
+            .test {
+                color: $some-color;
+            }
+        "#,
+        &settings,
+    )
+    .unwrap();
+
+    let class_name = compiled_style_sheet.class_names.get("test").unwrap();
+    assert!(class_name.starts_with("class-"));
+    assert_eq!(
+        compiled_style_sheet.css,
+        format!(".{class_name} {{\n  color: #69e69d;\n}}\n")
+    );
+}
+
+#[test]
+fn reuse_class_names_of_compile_time_style_sheet() {
+    turf::inline_style_sheet! {
+        .test {
+            color: red;
+        }
+    };
+
+    let compiled_style_sheet = turf::compile_with_class_names(
+        ".test { color: blue; } .other { color: green; }",
+        &turf::Settings::default(),
+        [("test", ClassName::TEST)],
+    )
+    .unwrap();
+
+    assert!(compiled_style_sheet
+        .css
+        .starts_with(&format!(".{}{{color:#00f}}", ClassName::TEST)));
+    assert_eq!(
+        compiled_style_sheet.class_names.get("test").unwrap(),
+        ClassName::TEST
+    );
+}
+
+#[test]
+fn import_packages_from_package_dirs() {
+    let settings = turf::Settings::builder()
+        .package_dir("../vendor")
+        .class_name_template("<original_name>")
+        .build()
+        .unwrap();
+
+    let compiled_style_sheet = turf::compile(
+        r#"
+            @use "~normalize.css" as *;
+            @use "~framework" as framework;
+            @use "~framework/scss/grid";
+
+            .test {
+                gap: framework.$primary;
+                margin: grid.$gutter;
+            }
+        "#,
+        &settings,
+    )
+    .unwrap();
+
+    assert_eq!(
+        compiled_style_sheet.css,
+        "html{line-height:1.15}.test{gap:24px;margin:24px}"
+    );
+}
@@ -0,0 +1,7 @@
+fn main() {
+    turf_build::Builder::new()
+        .load_path("../variables")
+        .css_file_name("turf.<hash>.css")
+        .compile_dir("src/styles")
+        .unwrap();
+}
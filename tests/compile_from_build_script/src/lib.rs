@@ -0,0 +1,57 @@
+#[cfg(test)]
+mod styles {
+    include!(concat!(env!("OUT_DIR"), "/turf.rs"));
+}
+
+#[test]
+fn compile_style_sheets_from_build_script() {
+    assert!(styles::button_primary::STYLE_SHEET.starts_with(".class-"));
+    assert!(styles::button_primary::STYLE_SHEET.ends_with(" {\n  color: #69e69d;\n}\n"));
+    assert!(styles::button_primary::STYLE_SHEET
+        .starts_with(&format!(".{}", styles::button_primary::ClassName::TEST)));
+    assert!(
+        styles::card::STYLE_SHEET.starts_with(&format!(".{}", styles::card::ClassName::CARD_TITLE))
+    );
+}
+
+#[test]
+fn bundle_contains_all_style_sheets() {
+    assert_eq!(
+        styles::STYLE_SHEET,
+        format!(
+            "{}{}",
+            styles::button_primary::STYLE_SHEET,
+            styles::card::STYLE_SHEET
+        )
+    );
+}
+
+#[test]
+fn bundle_file_name_contains_the_hash_of_its_content() {
+    let hash = styles::CSS_FILE_NAME
+        .strip_prefix("turf.")
+        .and_then(|file_name| file_name.strip_suffix(".css"))
+        .unwrap();
+
+    assert_eq!(hash.len(), 16);
+    assert!(hash.bytes().all(|byte| byte.is_ascii_hexdigit()));
+    assert_eq!(
+        std::fs::read_to_string(std::path::Path::new(env!("OUT_DIR")).join(styles::CSS_FILE_NAME))
+            .unwrap(),
+        styles::STYLE_SHEET
+    );
+}
+
+#[test]
+fn style_sheets_have_distinct_csp_hashes() {
+    assert!(styles::button_primary::STYLE_SHEET_CSP_HASH.starts_with("'sha256-"));
+    assert_ne!(
+        styles::button_primary::STYLE_SHEET_CSP_HASH,
+        styles::card::STYLE_SHEET_CSP_HASH
+    );
+}
+
+#[test]
+fn bundle_integrity_is_exposed() {
+    assert!(styles::CSS_FILE_INTEGRITY.starts_with("sha384-"));
+}
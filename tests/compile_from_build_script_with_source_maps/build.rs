@@ -0,0 +1,6 @@
+fn main() {
+    turf_build::Builder::new()
+        .css_file_name("turf.<hash>.css")
+        .compile_dir("src/styles")
+        .unwrap();
+}
@@ -0,0 +1,46 @@
+#[cfg(test)]
+mod styles {
+    include!(concat!(env!("OUT_DIR"), "/turf.rs"));
+}
+
+#[test]
+fn bundle_references_its_source_map() {
+    let css_file = std::path::Path::new(env!("OUT_DIR")).join(styles::CSS_FILE_NAME);
+
+    assert_eq!(
+        std::fs::read_to_string(&css_file).unwrap(),
+        styles::STYLE_SHEET
+    );
+    assert!(styles::STYLE_SHEET.ends_with(&format!(
+        "\n/*# sourceMappingURL={}.map */",
+        styles::CSS_FILE_NAME
+    )));
+    assert_eq!(
+        styles::CSS_FILE_INTEGRITY,
+        turf_internals::integrity_hash(styles::STYLE_SHEET.as_bytes())
+    );
+
+    let source_map = std::fs::read_to_string(css_file.with_extension("css.map")).unwrap();
+    assert!(source_map
+        .contains(r#""sources":["src/styles/button.scss.css","src/styles/card.scss.css"]"#));
+}
+
+#[test]
+fn style_sheets_embed_their_source_maps() {
+    for (style_sheet, csp_hash, class_name) in [
+        (
+            styles::button::STYLE_SHEET,
+            styles::button::STYLE_SHEET_CSP_HASH,
+            styles::button::ClassName::BUTTON,
+        ),
+        (
+            styles::card::STYLE_SHEET,
+            styles::card::STYLE_SHEET_CSP_HASH,
+            styles::card::ClassName::CARD,
+        ),
+    ] {
+        assert!(style_sheet.starts_with(&format!(".{class_name}")));
+        assert!(style_sheet.contains("\n/*# sourceMappingURL=data:application/json;base64,"));
+        assert_eq!(csp_hash, turf_internals::csp_hash(style_sheet));
+    }
+}
@@ -11,3 +11,52 @@ fn inline_scss_style() {
     assert!(STYLE_SHEET.ends_with(" {\n  color: #69e69d;\n}\n"));
     assert!(STYLE_SHEET.starts_with(&format!(".{}", ClassName::TEST)));
 }
+
+#[test]
+fn inline_css_style() {
+    turf::inline_style_sheet! {
+        syntax = "css";
+
+        .test {
+            color: red;
+        }
+    };
+    assert_eq!(
+        STYLE_SHEET,
+        format!(".{} {{\n  color: red;\n}}\n", ClassName::TEST)
+    );
+}
+
+#[test]
+fn inline_style_can_be_embedded_in_html() {
+    turf::inline_style_sheet! {
+        .test::after {
+            content: "</style><script>";
+        }
+    };
+    assert!(!STYLE_SHEET.to_lowercase().contains("</style"));
+    assert!(STYLE_SHEET.contains(r#"<\/style><script>"#));
+    assert!(STYLE_SHEET_CSP_HASH.starts_with("'sha256-"));
+    assert_eq!(
+        turf::style_src_directive_for(&[STYLE_SHEET_CSP_HASH, STYLE_SHEET_CSP_HASH]),
+        format!("style-src {STYLE_SHEET_CSP_HASH}")
+    );
+}
+
+#[test]
+fn csp_hashes_of_all_macros_are_registered() {
+    turf::inline_style_sheet! {
+        .registered {
+            color: red;
+        }
+    };
+    let (values_style_sheet, _) = turf::inline_style_sheet_values! {
+        .registered_values {
+            color: blue;
+        }
+    };
+
+    let directive = turf::style_src_directive();
+    assert!(directive.contains(STYLE_SHEET_CSP_HASH));
+    assert!(directive.contains(&turf_internals::csp_hash(values_style_sheet)));
+}
@@ -0,0 +1,31 @@
+#[cfg(test)]
+fn gunzip(content: &[u8]) -> String {
+    use std::io::Read;
+
+    let mut css = String::new();
+    flate2::read::GzDecoder::new(content)
+        .read_to_string(&mut css)
+        .unwrap();
+    css
+}
+
+#[test]
+fn style_sheet_embeds_the_compressed_css() {
+    turf::style_sheet!("src/style.scss");
+
+    assert_eq!(gunzip(STYLE_SHEET_GZIP), STYLE_SHEET);
+}
+
+#[test]
+fn values_macros_return_the_compressed_css() {
+    let (style_sheet, class_names, compressed) = turf::style_sheet_values!("src/style.scss");
+    assert_eq!(class_names.button, "button");
+    assert_eq!(gunzip(compressed.gzip), style_sheet);
+
+    let (style_sheet, _, compressed) = turf::inline_style_sheet_values! {
+        .card {
+            color: blue;
+        }
+    };
+    assert_eq!(gunzip(compressed.gzip), style_sheet);
+}
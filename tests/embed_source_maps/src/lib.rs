@@ -0,0 +1,10 @@
+#[test]
+fn source_maps_are_inlined_in_debug_builds() {
+    turf::style_sheet!("src/style.scss");
+
+    let (css, comment) = STYLE_SHEET.split_once('\n').unwrap();
+    assert_eq!(css, ".button{color:red}");
+    assert!(comment.starts_with("/*# sourceMappingURL=data:application/json;base64,"));
+    assert!(comment.ends_with(" */"));
+    assert_eq!(STYLE_SHEET_CSP_HASH, turf_internals::csp_hash(STYLE_SHEET));
+}
@@ -0,0 +1,105 @@
+/// Restores a style sheet modified by a test when dropped, even if the test panics
+#[cfg(test)]
+struct RestoreOnDrop {
+    path: &'static str,
+    original_content: String,
+}
+
+#[cfg(test)]
+impl RestoreOnDrop {
+    fn new(path: &'static str) -> Self {
+        Self {
+            path,
+            original_content: std::fs::read_to_string(path).unwrap(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl Drop for RestoreOnDrop {
+    fn drop(&mut self) {
+        let _ = std::fs::write(self.path, &self.original_content);
+    }
+}
+
+#[test]
+fn recompile_modified_style_sheet() {
+    turf::style_sheet!("src/mystyle.scss");
+    assert_eq!(STYLE_SHEET.current(), ".test{color:#333}");
+    assert_eq!(&*STYLE_SHEET, ".test{color:#333}");
+
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/src/mystyle.scss");
+    let _restore = RestoreOnDrop::new(path);
+
+    std::thread::sleep(std::time::Duration::from_millis(300));
+    std::fs::write(path, ".test { color: #444; }").unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(300));
+
+    assert_eq!(STYLE_SHEET.current(), ".test{color:#444}");
+    assert_eq!(STYLE_SHEET.embedded(), ".test{color:#333}");
+}
+
+#[test]
+fn values_macro_returns_hot_style_sheet() {
+    let (style_sheet, class_names) = turf::inline_style_sheet_values! {
+        .test {
+            color: red;
+        }
+    };
+
+    assert_eq!(style_sheet.current(), ".test{color:red}");
+    assert_eq!(class_names.test, "test");
+}
+
+#[cfg(test)]
+mod live_reload {
+    mod live {
+        turf::style_sheet!("src/livestyle.scss");
+    }
+
+    async fn next_frame(body: &mut turf::live_reload::LiveReloadBody) -> bytes::Bytes {
+        use http_body::Body;
+
+        std::future::poll_fn(|cx| std::pin::Pin::new(&mut *body).poll_frame(cx))
+            .await
+            .unwrap()
+            .unwrap()
+            .into_data()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn push_recompiled_style_sheet() {
+        use tower_service::Service;
+
+        let mut live_reload =
+            turf::live_reload::LiveReload::new("/turf-live-reload", [&live::STYLE_SHEET]);
+        assert!(live_reload
+            .client_script()
+            .contains(r#"new EventSource("/turf-live-reload")"#));
+
+        let response = live_reload.call(http::Request::new(())).await.unwrap();
+        assert_eq!(
+            response.headers()[http::header::CONTENT_TYPE],
+            "text/event-stream"
+        );
+        let mut body = response.into_body();
+
+        let retry = next_frame(&mut body).await;
+        assert_eq!(retry, "retry: 1000\n\n");
+
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/src/livestyle.scss");
+        let _restore = crate::RestoreOnDrop::new(path);
+        std::thread::sleep(std::time::Duration::from_millis(300));
+        std::fs::write(path, ".live { color: #444; }").unwrap();
+        let event = next_frame(&mut body).await;
+
+        assert_eq!(
+            event,
+            format!(
+                "event: turf-style-sheet\ndata: {}\ndata: .live{{color:#444}}\n\n",
+                live::STYLE_SHEET.hash()
+            )
+        );
+    }
+}
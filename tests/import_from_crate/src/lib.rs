@@ -0,0 +1,16 @@
+#[test]
+fn import_style_sheet_from_dependency_crate() {
+    turf::inline_style_sheet! {
+        @use "crate:shared_styles/tokens/colors";
+
+        .test {
+            color: colors.$primary;
+            gap: colors.$gap;
+        }
+    };
+
+    assert_eq!(
+        STYLE_SHEET,
+        format!(".{}{{color:#69e69d;gap:12px}}", ClassName::TEST)
+    );
+}
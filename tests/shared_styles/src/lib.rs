@@ -0,0 +1 @@
+//! Style sheets exported to the crates depending on this crate
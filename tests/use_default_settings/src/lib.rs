@@ -5,3 +5,32 @@ fn use_default_settings() {
     assert!(STYLE_SHEET.starts_with(".class-"));
     assert!(STYLE_SHEET.starts_with(&format!(".{}", ClassName::TEST)));
 }
+
+#[test]
+fn pick_syntax_from_file_extension() {
+    mod sass {
+        turf::style_sheet!("src/legacy.sass");
+    }
+    mod css {
+        turf::style_sheet!("src/plain.css");
+    }
+
+    assert_eq!(
+        sass::STYLE_SHEET,
+        format!(
+            ".{}{{color:#333}}.{} .{}{{color:red}}",
+            sass::ClassName::TEST,
+            sass::ClassName::TEST,
+            sass::ClassName::INNER
+        )
+    );
+    assert_eq!(
+        css::STYLE_SHEET,
+        format!(
+            ".{}{{color:#333}}.{}>.{}{{color:red}}",
+            css::ClassName::TEST,
+            css::ClassName::TEST,
+            css::ClassName::INNER
+        )
+    );
+}
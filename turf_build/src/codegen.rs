@@ -0,0 +1,108 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+
+use convert_case::{Case, Casing};
+use turf_internals::CompiledStyleSheet;
+
+pub fn module_name(relative_path: &Path) -> String {
+    let name = relative_path
+        .with_extension("")
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().to_case(Case::Snake))
+        .collect::<Vec<_>>()
+        .join("_")
+        .replace(|c: char| !c.is_ascii_alphanumeric() && c != '_', "_");
+
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{name}")
+    } else {
+        name
+    }
+}
+
+pub fn create_module(
+    css_file: &Path,
+    css_file_integrity: &str,
+    modules: &BTreeMap<String, (PathBuf, CompiledStyleSheet)>,
+) -> proc_macro2::TokenStream {
+    let css_file_name = css_file
+        .file_name()
+        .map(|file_name| file_name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let css_file = css_file.display().to_string();
+    let style_sheet_modules = modules
+        .iter()
+        .map(|(module_name, (path, compiled_style_sheet))| {
+            let module_name = quote::format_ident!("{}", module_name);
+            let doc = format!("Generated from `{}`", path.display());
+            let css = compiled_style_sheet.css_with_inline_source_map();
+            let csp_hash = turf_internals::csp_hash(&css);
+            let class_names = create_classes_structure(&compiled_style_sheet.class_names);
+
+            quote::quote! {
+                #[doc=#doc]
+                pub mod #module_name {
+                    pub static STYLE_SHEET: &str = #css;
+                    pub static STYLE_SHEET_CSP_HASH: &str = #csp_hash;
+                    #class_names
+                }
+            }
+        });
+
+    quote::quote! {
+        pub static STYLE_SHEET: &str = include_str!(#css_file);
+        pub static CSS_FILE_NAME: &str = #css_file_name;
+        pub static CSS_FILE_INTEGRITY: &str = #css_file_integrity;
+        #(#style_sheet_modules)*
+    }
+}
+
+fn create_classes_structure(classes: &HashMap<String, String>) -> proc_macro2::TokenStream {
+    let classes: BTreeMap<&String, &String> = classes.iter().collect();
+
+    let original_class_names: Vec<proc_macro2::Ident> = classes
+        .keys()
+        .map(|class| class.to_case(Case::ScreamingSnake))
+        .map(|class| quote::format_ident!("{}", class.as_str().to_uppercase()))
+        .collect();
+
+    let randomized_class_names: Vec<&&String> = classes.values().collect();
+
+    let doc = original_class_names
+        .iter()
+        .zip(randomized_class_names.iter())
+        .fold(String::new(), |mut doc, (variable, class_name)| {
+            doc.push_str(&format!("{} = \"{}\"\n", variable, class_name));
+            doc
+        });
+
+    quote::quote! {
+        #[doc=#doc]
+        pub struct ClassName;
+        impl ClassName {
+            #(pub const #original_class_names: &str = #randomized_class_names;)*
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::module_name;
+
+    #[test]
+    fn module_name_from_nested_path() {
+        assert_eq!(
+            module_name(Path::new("button/PrimaryButton.scss")),
+            "button_primary_button"
+        );
+    }
+
+    #[test]
+    fn module_name_starting_with_digit() {
+        assert_eq!(module_name(Path::new("2col-layout.scss")), "_2_col_layout");
+    }
+}
@@ -0,0 +1,263 @@
+//! Compile turf style sheets from a `build.rs` build script.
+//!
+//! The proc macros of `turf` compile a style sheet whenever the invoking module is expanded. With `turf_build`, all style sheets of a directory are compiled once per build script run instead.
+//! The resulting CSS bundle is written into `OUT_DIR` together with a Rust module that contains the `STYLE_SHEET` and `ClassName` items for every compiled style sheet.
+//!
+//! Settings are read from the `[package.metadata.turf]` and `[package.metadata.turf-dev]` keys of the `Cargo.toml` in the same way as with the macros. The `file_output` key only applies to the macros and is reported as a warning.
+//!
+//! **Usage:**
+//!
+//! ```rust,ignore
+//! // build.rs
+//! fn main() {
+//!     turf_build::Builder::new()
+//!         .load_path("path/to/shared/scss/files")
+//!         .compile_dir("src/styles")
+//!         .unwrap();
+//! }
+//! ```
+//!
+//! ```rust,ignore
+//! // src/main.rs
+//! mod styles {
+//!     include!(concat!(env!("OUT_DIR"), "/turf.rs"));
+//! }
+//!
+//! // the CSS of all style sheets
+//! let bundle = styles::STYLE_SHEET;
+//! // the file name of the CSS bundle in `OUT_DIR` and its Subresource Integrity hash
+//! let bundle_file_name = styles::CSS_FILE_NAME;
+//! let bundle_integrity = styles::CSS_FILE_INTEGRITY;
+//! // the CSS and class names of `src/styles/button/primary.scss`
+//! let style_sheet = styles::button_primary::STYLE_SHEET;
+//! let class_name = styles::button_primary::ClassName::SOME_CLASS;
+//! ```
+
+mod codegen;
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+use turf_internals::{CompiledStyleSheet, Compiler, Settings, StyleSheetKind};
+
+static CSS_FILE_NAME: &str = "turf.css";
+static MODULE_FILE_NAME: &str = "turf.rs";
+static ASSETS_DIR_NAME: &str = "assets";
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Turf(#[from] turf_internals::Error),
+    #[error(transparent)]
+    Settings(#[from] turf_internals::SettingsError),
+    #[error("could not determine the {0} directory - {1}")]
+    EnvDir(&'static str, std::env::VarError),
+    #[error("error reading style sheet directory '{0}' - {1}")]
+    ReadDir(PathBuf, std::io::Error),
+    #[error("error writing output file '{0}' - {1}")]
+    Write(PathBuf, std::io::Error),
+    #[error(transparent)]
+    CssFile(#[from] turf_internals::CssFileWriteError),
+    #[error("the style sheets '{0}' and '{1}' both map to the module name '{2}'")]
+    ModuleNameCollision(PathBuf, PathBuf, String),
+}
+
+/// The files written by the [`Builder`]
+#[derive(Debug)]
+pub struct BuildOutput {
+    /// The CSS bundle containing the styles of all compiled style sheets
+    pub css_file: PathBuf,
+    /// The Subresource Integrity hash of the CSS bundle, e.g. `sha384-…`
+    pub css_file_integrity: String,
+    /// The Rust module to be used with `include!`
+    pub module_file: PathBuf,
+    /// The directory the assets referenced with `url()` are copied into, if any were referenced
+    pub assets_dir: Option<PathBuf>,
+}
+
+/// Compiles style sheets and writes the results into the output directory of the build script
+#[derive(Debug, Default)]
+pub struct Builder {
+    load_paths: Vec<PathBuf>,
+    out_dir: Option<PathBuf>,
+    css_file_name: Option<String>,
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a load path in addition to the `load_paths` specified in the `Cargo.toml`
+    pub fn load_path<P>(mut self, path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.load_paths.push(path.into());
+        self
+    }
+
+    /// Overrides the output directory which defaults to the `OUT_DIR` of the build script
+    pub fn out_dir<P>(mut self, path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.out_dir = Some(path.into());
+        self
+    }
+
+    /// Overrides the file name of the CSS bundle which defaults to `turf.css`
+    ///
+    /// A `<hash>` in the file name is replaced with a hash of the CSS, e.g. `turf.<hash>.css`. Bundles written by earlier builds are removed and the final file name is available as `CSS_FILE_NAME` in the generated module.
+    pub fn css_file_name<S>(mut self, file_name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.css_file_name = Some(file_name.into());
+        self
+    }
+
+    /// Compiles every SCSS, Sass and CSS file in `dir` and its subdirectories
+    ///
+    /// Partials (files with a leading underscore) are not compiled on their own. Every style sheet gets its own module in the generated Rust file, named after its path relative to `dir`.
+    pub fn compile_dir<P>(self, dir: P) -> Result<BuildOutput, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let manifest_dir = env_dir("CARGO_MANIFEST_DIR")?;
+        let dir = manifest_dir.join(dir);
+        let out_dir = match self.out_dir {
+            Some(ref out_dir) => out_dir.clone(),
+            None => env_dir("OUT_DIR")?,
+        };
+
+        let settings = Settings::get()?.with_load_paths(self.load_paths.clone());
+        if settings.has_file_output() {
+            println!("cargo:warning=the `file_output` settings are ignored by turf_build, the style sheets are written into a single CSS bundle in the output directory");
+        }
+        let cache_dir = settings.cache_dir(&manifest_dir);
+        let mut compiler = Compiler::new(settings, &manifest_dir);
+
+        if let Some(cache_dir) = cache_dir {
+            compiler = compiler.with_cache_dir(cache_dir);
+        }
+
+        let mut style_sheet_paths = Vec::new();
+        collect_style_sheet_paths(&dir, &mut style_sheet_paths)?;
+        style_sheet_paths.sort();
+
+        let mut modules: BTreeMap<String, (PathBuf, CompiledStyleSheet)> = BTreeMap::new();
+
+        for path in style_sheet_paths {
+            let module_name = codegen::module_name(path.strip_prefix(&dir).unwrap_or(&path));
+
+            if let Some((other_path, _)) = modules.get(&module_name) {
+                return Err(Error::ModuleNameCollision(
+                    other_path.clone(),
+                    path,
+                    module_name,
+                ));
+            }
+
+            let compiled_style_sheet = compiler.compile(StyleSheetKind::File(path.clone()))?;
+
+            modules.insert(module_name, (path, compiled_style_sheet));
+        }
+
+        let style_sheets = modules
+            .values()
+            .map(|(_, compiled_style_sheet)| compiled_style_sheet)
+            .collect::<Vec<_>>();
+        let (css_file, bundle) = turf_internals::write_combined_css_file(
+            &out_dir.join(self.css_file_name.as_deref().unwrap_or(CSS_FILE_NAME)),
+            &style_sheets,
+            &[],
+        )?;
+
+        let assets = modules
+            .values()
+            .flat_map(|(_, compiled_style_sheet)| &compiled_style_sheet.assets)
+            .collect::<Vec<_>>();
+        let assets_dir = (!assets.is_empty()).then(|| out_dir.join(ASSETS_DIR_NAME));
+        if let Some(ref assets_dir) = assets_dir {
+            std::fs::create_dir_all(assets_dir)
+                .map_err(|error| Error::Write(assets_dir.clone(), error))?;
+
+            for asset in assets {
+                let target_path = assets_dir.join(&asset.file_name);
+                std::fs::copy(&asset.source, &target_path)
+                    .map_err(|error| Error::Write(target_path, error))?;
+            }
+        }
+
+        let module_file = out_dir.join(MODULE_FILE_NAME);
+        let css_file_integrity = turf_internals::integrity_hash(bundle.as_bytes());
+        let module = codegen::create_module(&css_file, &css_file_integrity, &modules);
+        std::fs::write(&module_file, module.to_string())
+            .map_err(|error| Error::Write(module_file.clone(), error))?;
+
+        println!("cargo:rerun-if-changed=Cargo.toml");
+        println!("cargo:rerun-if-changed={}", dir.display());
+        for load_path in compiler
+            .settings()
+            .canonicalized_load_paths(&manifest_dir)
+            .map_err(turf_internals::Error::from)?
+        {
+            println!("cargo:rerun-if-changed={}", load_path.display());
+        }
+        // imports from outside of the load paths, e.g. `crate:` and `~package` imports, `url()` assets and the lock file
+        let dependencies = modules
+            .values()
+            .flat_map(|(_, compiled_style_sheet)| &compiled_style_sheet.dependencies)
+            .collect::<BTreeSet<_>>();
+        for dependency in dependencies {
+            println!("cargo:rerun-if-changed={}", dependency.display());
+        }
+
+        Ok(BuildOutput {
+            css_file,
+            css_file_integrity,
+            module_file,
+            assets_dir,
+        })
+    }
+}
+
+fn env_dir(name: &'static str) -> Result<PathBuf, Error> {
+    std::env::var(name)
+        .map(PathBuf::from)
+        .map_err(|error| Error::EnvDir(name, error))
+}
+
+fn collect_style_sheet_paths(dir: &Path, result: &mut Vec<PathBuf>) -> Result<(), Error> {
+    let entries =
+        std::fs::read_dir(dir).map_err(|error| Error::ReadDir(dir.to_path_buf(), error))?;
+
+    for entry in entries {
+        let path = entry
+            .map_err(|error| Error::ReadDir(dir.to_path_buf(), error))?
+            .path();
+
+        if path.is_dir() {
+            collect_style_sheet_paths(&path, result)?;
+        } else if is_style_sheet(&path) {
+            result.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+fn is_style_sheet(path: &Path) -> bool {
+    let is_partial = path
+        .file_name()
+        .and_then(|file_name| file_name.to_str())
+        .is_some_and(|file_name| file_name.starts_with('_'));
+    let is_style_sheet = path
+        .extension()
+        .is_some_and(|extension| extension == "scss" || extension == "sass" || extension == "css");
+
+    is_style_sheet && !is_partial
+}
@@ -0,0 +1,193 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+use base64::Engine;
+use lightningcss::{
+    printer::PrinterOptions,
+    stylesheet::{ParserOptions, StyleSheet},
+    values::url::Url,
+    visit_types,
+    visitor::{Visit, VisitTypes, Visitor},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{settings::AssetSettings, StyleSheetKind};
+
+#[derive(Debug, thiserror::Error)]
+pub enum AssetError {
+    #[error("asset '{0}' not found relative to '{1}' or in the load paths")]
+    NotFound(String, PathBuf),
+    #[error("error reading asset '{0}' - {1}")]
+    Read(PathBuf, std::io::Error),
+    #[error("error processing css - {0}")]
+    Css(String),
+}
+
+/// An asset referenced by the style sheet, which is served under a content-hashed file name
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Asset {
+    /// The canonicalized path of the referenced file
+    pub source: PathBuf,
+    /// The content-hashed file name the `url()` was rewritten to
+    pub file_name: String,
+}
+
+/// Rewrites the `url()` references to local files
+///
+/// Files up to the inline limit become data URIs, all other files are referenced by their content-hashed file name behind the URL prefix.
+struct AssetVisitor<'a> {
+    style_sheet_dir: &'a Path,
+    load_paths: &'a [PathBuf],
+    settings: &'a AssetSettings,
+    assets: Vec<Asset>,
+    read_files: BTreeSet<PathBuf>,
+}
+
+impl<'a> AssetVisitor<'a> {
+    fn resolve(&self, url: &str) -> Result<PathBuf, AssetError> {
+        let relative_path = self.style_sheet_dir.join(url);
+        if relative_path.is_file() {
+            return Ok(relative_path);
+        }
+
+        self.load_paths
+            .iter()
+            .map(|load_path| load_path.join(url))
+            .find(|path| path.is_file())
+            .ok_or_else(|| AssetError::NotFound(url.to_owned(), self.style_sheet_dir.to_path_buf()))
+    }
+
+    fn rewrite(&mut self, url: &str) -> Result<Option<String>, AssetError> {
+        if is_external(url) {
+            return Ok(None);
+        }
+
+        // the query and fragment are not part of the file name, e.g. in `font.eot?#iefix`
+        let (path, suffix) = url.split_at(url.find(['?', '#']).unwrap_or(url.len()));
+        let path = self.resolve(path)?;
+        let content = std::fs::read(&path).map_err(|e| AssetError::Read(path.clone(), e))?;
+        let path = path.canonicalize().unwrap_or(path);
+        self.read_files.insert(path.clone());
+
+        // a data URI can't carry the fragment, which SVG sprites rely on
+        if suffix.is_empty() && (content.len() as u64) < self.settings.inline_limit {
+            return Ok(Some(format!(
+                "data:{};base64,{}",
+                mime_type(&path),
+                base64::engine::general_purpose::STANDARD.encode(&content)
+            )));
+        }
+
+        let file_name = hashed_file_name(&path, &content);
+        let rewritten_url = format!("{}{file_name}{suffix}", self.settings.url_prefix);
+
+        if !self.assets.iter().any(|asset| asset.source == path) {
+            self.assets.push(Asset {
+                source: path,
+                file_name,
+            });
+        }
+
+        Ok(Some(rewritten_url))
+    }
+}
+
+impl<'a, 'i> Visitor<'i> for AssetVisitor<'a> {
+    type Error = AssetError;
+
+    fn visit_types(&self) -> VisitTypes {
+        visit_types!(URLS)
+    }
+
+    fn visit_url(&mut self, url: &mut Url<'i>) -> Result<(), Self::Error> {
+        if let Some(rewritten_url) = self.rewrite(&url.url)? {
+            url.url = rewritten_url.into();
+        }
+
+        Ok(())
+    }
+}
+
+/// Rewrites the `url()` references of the CSS and returns it together with the referenced assets and the files that were read
+pub fn process_asset_urls<'i>(
+    css: &'i str,
+    style_sheet: &StyleSheetKind,
+    base_dir: &Path,
+    load_paths: &[PathBuf],
+    settings: &AssetSettings,
+    parser_options: ParserOptions<'_, 'i>,
+) -> Result<(String, Vec<Asset>, Vec<PathBuf>), AssetError> {
+    let style_sheet_dir = match style_sheet {
+        StyleSheetKind::File(path) => path.parent().unwrap_or(base_dir),
+        StyleSheetKind::Inline(_) => base_dir,
+    };
+
+    let mut parsed_style_sheet =
+        StyleSheet::parse(css, parser_options).map_err(|e| AssetError::Css(e.to_string()))?;
+
+    let mut visitor = AssetVisitor {
+        style_sheet_dir,
+        load_paths,
+        settings,
+        assets: Vec::new(),
+        read_files: BTreeSet::new(),
+    };
+    parsed_style_sheet.visit(&mut visitor)?;
+
+    let css = parsed_style_sheet
+        .to_css(PrinterOptions::default())
+        .map_err(|e| AssetError::Css(e.to_string()))?
+        .code;
+
+    Ok((
+        css,
+        visitor.assets,
+        visitor.read_files.into_iter().collect(),
+    ))
+}
+
+fn is_external(url: &str) -> bool {
+    url.is_empty()
+        || url.starts_with('#')
+        || url.starts_with('/')
+        || url.starts_with("data:")
+        || url.contains("://")
+}
+
+/// Appends a hash of the content to the file stem, e.g. `logo.3f2a1b0c9d8e7f6a.svg`
+fn hashed_file_name(path: &Path, content: &[u8]) -> String {
+    let hash = xxhash_rust::xxh3::xxh3_64(content);
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("asset");
+
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some(extension) => format!("{stem}.{hash:016x}.{extension}"),
+        None => format!("{stem}.{hash:016x}"),
+    }
+}
+
+fn mime_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+
+    match extension.as_deref() {
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("avif") => "image/avif",
+        Some("ico") => "image/x-icon",
+        Some("woff") => "font/woff",
+        Some("woff2") => "font/woff2",
+        Some("ttf") => "font/ttf",
+        Some("otf") => "font/otf",
+        _ => "application/octet-stream",
+    }
+}
@@ -0,0 +1,209 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use lightningcss::{
+    bundler::{Bundler, SourceProvider},
+    printer::PrinterOptions,
+    rules::CssRule,
+    stylesheet::{ParserFlags, ParserOptions, StyleSheet},
+    traits::ToCss,
+};
+
+use crate::{
+    settings::GlobalSources,
+    transformer::{GLOBAL_SOURCE_END_RULE, GLOBAL_SOURCE_START_RULE},
+    Settings, StyleSheetKind,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum BundlingError {
+    #[error("error reading imported css file '{0}' - {1}")]
+    Read(PathBuf, std::io::Error),
+    #[error(
+        "remote import '{0}' can only be used in the style sheet itself, not in an imported file"
+    )]
+    NestedRemoteImport(String),
+    #[error("error parsing css - {0}")]
+    Parse(String),
+    #[error("error bundling css imports - {0}")]
+    Bundle(String),
+    #[error("error printing bundled css - {0}")]
+    Print(String),
+}
+
+/// The file name the compiled CSS of an inline style sheet is served under, imports are resolved relative to the base directory
+static INLINE_ENTRY_FILE_NAME: &str = "turf-inline-style-sheet.css";
+
+/// Provides the bundler with the compiled style sheet and the imported files
+///
+/// Imports are resolved relative to the importing file and then against the load paths. Every file read is recorded as dependency of the style sheet.
+struct ImportProvider {
+    entry_path: PathBuf,
+    entry_css: String,
+    base_dir: PathBuf,
+    load_paths: Vec<PathBuf>,
+    global_sources: GlobalSources,
+    parser_flags: ParserFlags,
+    contents: elsa::sync::FrozenVec<String>,
+    read_files: Mutex<BTreeSet<PathBuf>>,
+}
+
+impl SourceProvider for ImportProvider {
+    type Error = BundlingError;
+
+    fn read<'a>(&'a self, file: &Path) -> Result<&'a str, Self::Error> {
+        if file == self.entry_path {
+            return Ok(&self.entry_css);
+        }
+
+        let mut content = std::fs::read_to_string(file)
+            .map_err(|e| BundlingError::Read(file.to_path_buf(), e))?;
+        let path = std::fs::canonicalize(file).unwrap_or_else(|_| file.to_path_buf());
+
+        if !self.global_sources.is_empty() && self.global_sources.is_match(&self.base_dir, &path) {
+            content = enclose_in_global_source_markers(&content, &self.parser_flags)?;
+        }
+
+        self.read_files.lock().unwrap().insert(path);
+
+        // the contents are kept until the provider is dropped, the bundler borrows them until then
+        Ok(self.contents.push_get(content))
+    }
+
+    fn resolve(&self, specifier: &str, originating_file: &Path) -> Result<PathBuf, Self::Error> {
+        if is_remote(specifier) {
+            return Err(BundlingError::NestedRemoteImport(specifier.to_owned()));
+        }
+
+        let relative_path = originating_file.with_file_name(specifier);
+        if relative_path.is_file() {
+            return Ok(relative_path);
+        }
+
+        Ok(self
+            .load_paths
+            .iter()
+            .map(|load_path| load_path.join(specifier))
+            .find(|path| path.is_file())
+            .unwrap_or(relative_path))
+    }
+}
+
+/// Inlines the local `@import` rules of the compiled CSS and returns it together with the imported files
+///
+/// Conditions of an import are kept by wrapping the imported rules in `@layer`, `@media` and `@supports` blocks. Remote imports are not bundled and stay at the top of the style sheet.
+pub fn bundle_css_imports(
+    css: &str,
+    style_sheet: &StyleSheetKind,
+    base_dir: &Path,
+    load_paths: Vec<PathBuf>,
+    settings: &Settings,
+) -> Result<(String, Vec<PathBuf>), BundlingError> {
+    let mut entry = StyleSheet::parse(css, settings.parser_options())
+        .map_err(|e| BundlingError::Parse(e.to_string()))?;
+
+    // the bundler can't fetch remote style sheets, so they are set aside and printed in front of the bundle
+    let mut remote_imports = String::new();
+    for rule in &entry.rules.0 {
+        if let CssRule::Import(import) = rule {
+            if is_remote(&import.url) {
+                remote_imports.push_str(
+                    &import
+                        .to_css_string(PrinterOptions::default())
+                        .map_err(|e| BundlingError::Print(e.to_string()))?,
+                );
+                remote_imports.push('\n');
+            }
+        }
+    }
+    entry
+        .rules
+        .0
+        .retain(|rule| !matches!(rule, CssRule::Import(import) if is_remote(&import.url)));
+
+    let entry_css = entry
+        .to_css(PrinterOptions::default())
+        .map_err(|e| BundlingError::Print(e.to_string()))?
+        .code;
+
+    let entry_path = match style_sheet {
+        StyleSheetKind::File(path) => path.clone(),
+        StyleSheetKind::Inline(_) => base_dir.join(INLINE_ENTRY_FILE_NAME),
+    };
+
+    let provider = ImportProvider {
+        entry_path: entry_path.clone(),
+        entry_css,
+        base_dir: base_dir.to_path_buf(),
+        load_paths,
+        global_sources: settings.global_sources.clone(),
+        parser_flags: settings.drafts.0.clone(),
+        contents: elsa::sync::FrozenVec::new(),
+        read_files: Mutex::new(BTreeSet::new()),
+    };
+
+    let bundled_css = {
+        let mut bundler = Bundler::new(&provider, None, settings.parser_options());
+        let bundle = bundler
+            .bundle(&entry_path)
+            .map_err(|e| BundlingError::Bundle(e.to_string()))?;
+
+        bundle
+            .to_css(PrinterOptions::default())
+            .map_err(|e| BundlingError::Print(e.to_string()))?
+            .code
+    };
+
+    let imported_files = provider.read_files.into_inner().unwrap();
+
+    Ok((
+        remote_imports + &bundled_css,
+        imported_files.into_iter().collect(),
+    ))
+}
+
+/// Places the global source markers after the leading `@import` rules of a file, which have to stay at its top
+fn enclose_in_global_source_markers(
+    content: &str,
+    parser_flags: &ParserFlags,
+) -> Result<String, BundlingError> {
+    let parser_options = ParserOptions {
+        flags: parser_flags.clone(),
+        ..Default::default()
+    };
+    let style_sheet = StyleSheet::parse(content, parser_options)
+        .map_err(|e| BundlingError::Parse(e.to_string()))?;
+
+    let mut enclosed_content = String::new();
+    let mut markers_started = false;
+    for rule in &style_sheet.rules.0 {
+        let is_leading_rule = matches!(
+            rule,
+            CssRule::Import(_) | CssRule::LayerStatement(_) | CssRule::Ignored
+        );
+        if !markers_started && !is_leading_rule {
+            enclosed_content.push_str(&format!("@{GLOBAL_SOURCE_START_RULE};\n"));
+            markers_started = true;
+        }
+
+        enclosed_content.push_str(
+            &rule
+                .to_css_string(PrinterOptions::default())
+                .map_err(|e| BundlingError::Print(e.to_string()))?,
+        );
+        enclosed_content.push('\n');
+    }
+
+    if markers_started {
+        enclosed_content.push_str(&format!("@{GLOBAL_SOURCE_END_RULE};\n"));
+    }
+
+    Ok(enclosed_content)
+}
+
+fn is_remote(url: &str) -> bool {
+    url.starts_with("//") || url.starts_with("data:") || url.contains("://")
+}
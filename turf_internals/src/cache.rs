@@ -0,0 +1,142 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Asset, CompiledStyleSheet, Settings, StyleSheetKind, Syntax};
+
+#[derive(Debug, thiserror::Error)]
+pub enum CacheError {
+    #[error("error writing compile cache entry '{0}' - {1}")]
+    Write(PathBuf, std::io::Error),
+    #[error("error serializing compile cache entry - {0}")]
+    Serialization(#[from] toml::ser::Error),
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedDependency {
+    path: PathBuf,
+    hash: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    css: String,
+    hash: String,
+    class_names: HashMap<String, String>,
+    dependencies: Vec<CachedDependency>,
+    #[serde(default)]
+    assets: Vec<Asset>,
+    #[serde(default)]
+    source_map: Option<String>,
+    /// The import candidates that didn't exist, a file created at one of them shadows an imported file
+    #[serde(default)]
+    missing_files: Vec<PathBuf>,
+}
+
+/// The cache entry of a single style sheet compilation
+///
+/// The entry is looked up by a key computed from the style sheet, the settings affecting the compilation and the pinned class names. It is only used if none of the files read during the cached compilation changed since, and none of the import candidates that were missing has been created.
+pub struct CompileCache {
+    entry_path: PathBuf,
+}
+
+impl CompileCache {
+    pub fn new(
+        directory: &Path,
+        style_sheet: &StyleSheetKind,
+        syntax: Syntax,
+        settings: &Settings,
+        base_dir: &Path,
+        pinned_class_names: &HashMap<String, String>,
+    ) -> Self {
+        let pinned_class_names = pinned_class_names.iter().collect::<BTreeMap<_, _>>();
+        let fingerprint = format!(
+            "{}\0{:?}\0{}\0{:?}\0{:?}\0{:?}",
+            env!("CARGO_PKG_VERSION"),
+            syntax,
+            settings.compile_fingerprint(),
+            base_dir,
+            pinned_class_names,
+            style_sheet,
+        );
+        let key = xxhash_rust::xxh3::xxh3_128(fingerprint.as_bytes());
+
+        Self {
+            entry_path: directory.join(format!("{key:x}.toml")),
+        }
+    }
+
+    pub fn get(&self, style_sheet: &StyleSheetKind) -> Option<CompiledStyleSheet> {
+        let entry: CacheEntry =
+            toml::de::from_str(&std::fs::read_to_string(&self.entry_path).ok()?).ok()?;
+
+        let dependencies_unchanged = entry
+            .dependencies
+            .iter()
+            .all(|dependency| hash_file(&dependency.path).as_ref() == Some(&dependency.hash));
+        let candidates_missing = entry.missing_files.iter().all(|path| !path.exists());
+
+        (dependencies_unchanged && candidates_missing).then(|| CompiledStyleSheet {
+            css: entry.css,
+            hash: entry.hash,
+            class_names: entry.class_names,
+            original_style_sheet: style_sheet.clone(),
+            dependencies: entry
+                .dependencies
+                .into_iter()
+                .map(|dependency| dependency.path)
+                .collect(),
+            assets: entry.assets,
+            compressed: Vec::new(),
+            source_map: entry.source_map,
+        })
+    }
+
+    pub fn store(
+        &self,
+        compiled_style_sheet: &CompiledStyleSheet,
+        missing_files: &[PathBuf],
+    ) -> Result<(), CacheError> {
+        let entry = CacheEntry {
+            css: compiled_style_sheet.css.clone(),
+            hash: compiled_style_sheet.hash.clone(),
+            class_names: compiled_style_sheet.class_names.clone(),
+            assets: compiled_style_sheet.assets.clone(),
+            source_map: compiled_style_sheet.source_map.clone(),
+            missing_files: missing_files.to_vec(),
+            dependencies: compiled_style_sheet
+                .dependencies
+                .iter()
+                .filter_map(|path| {
+                    hash_file(path).map(|hash| CachedDependency {
+                        path: path.clone(),
+                        hash,
+                    })
+                })
+                .collect(),
+        };
+
+        let write_error = |error| CacheError::Write(self.entry_path.clone(), error);
+
+        if let Some(directory) = self.entry_path.parent() {
+            std::fs::create_dir_all(directory).map_err(write_error)?;
+        }
+
+        // written to a temporary file first, so concurrent compilations never read partial entries
+        let temporary_path = self
+            .entry_path
+            .with_extension(format!("{}.tmp", std::process::id()));
+        std::fs::write(&temporary_path, toml::to_string(&entry)?).map_err(write_error)?;
+        std::fs::rename(&temporary_path, &self.entry_path).map_err(write_error)?;
+
+        Ok(())
+    }
+}
+
+fn hash_file(path: &Path) -> Option<String> {
+    let hash = xxhash_rust::xxh3::xxh3_128(&std::fs::read(path).ok()?);
+    Some(format!("{hash:x}"))
+}
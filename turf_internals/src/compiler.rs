@@ -0,0 +1,473 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use crate::{
+    assets, bundling, cache::CompileCache, css_compilation, file_output, hashing, path_utils,
+    transformer, CompiledStyleSheet, Settings, StyleSheetKind, StyleSheetOptions, Syntax,
+};
+
+/// Compiles style sheets with explicit settings
+///
+/// Unlike the macros, the compiler does not read the Cargo manifest or any environment variables. Relative style sheet paths and load paths are resolved against the base directory. The compiler holds no global state, so any number of compilers can be used at the same time.
+///
+/// ```rust,ignore
+/// let settings = turf_internals::Settings::builder()
+///     .minify(false)
+///     .load_path("shared/scss")
+///     .build()?;
+/// let compiler = turf_internals::Compiler::new(settings, "/path/to/project");
+///
+/// let compiled_style_sheet = compiler.compile(StyleSheetKind::File("styles/app.scss".into()))?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct Compiler {
+    settings: Settings,
+    base_dir: PathBuf,
+    pinned_class_names: HashMap<String, String>,
+    cache_dir: Option<PathBuf>,
+}
+
+impl Compiler {
+    pub fn new<P>(settings: Settings, base_dir: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        Self {
+            settings,
+            base_dir: base_dir.into(),
+            pinned_class_names: HashMap::new(),
+            cache_dir: None,
+        }
+    }
+
+    /// Reuses an existing mapping of original to generated class names
+    ///
+    /// Classes contained in the mapping keep their generated names instead of receiving new ones. This allows compiling additional style sheets that target the classes of an already compiled style sheet.
+    pub fn with_class_names<I, K, V>(mut self, class_names: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.pinned_class_names.extend(
+            class_names
+                .into_iter()
+                .map(|(original, generated)| (original.into(), generated.into())),
+        );
+        self
+    }
+
+    /// Caches compiled style sheets in `cache_dir`
+    ///
+    /// A cached style sheet is reused as long as the settings and all files read during its compilation stay unchanged, and no file is created where an import was looked for.
+    pub fn with_cache_dir<P>(mut self, cache_dir: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.cache_dir = Some(cache_dir.into());
+        self
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    pub fn compile(&self, style_sheet: StyleSheetKind) -> Result<CompiledStyleSheet, crate::Error> {
+        self.compile_with_options(style_sheet, &StyleSheetOptions::default())
+    }
+
+    pub fn compile_with_options(
+        &self,
+        style_sheet: StyleSheetKind,
+        options: &StyleSheetOptions,
+    ) -> Result<CompiledStyleSheet, crate::Error> {
+        let style_sheet = match style_sheet {
+            StyleSheetKind::File(path) => {
+                if path.as_os_str().is_empty() {
+                    return Err(crate::Error::NoInputFile);
+                };
+                StyleSheetKind::File(path_utils::canonicalize(&self.base_dir, path)?)
+            }
+            StyleSheetKind::Inline(inline_style_sheet) => {
+                StyleSheetKind::Inline(inline_style_sheet)
+            }
+        };
+
+        let syntax = options.syntax.unwrap_or(match style_sheet {
+            StyleSheetKind::File(ref path) => Syntax::for_path(path),
+            StyleSheetKind::Inline(_) => Syntax::Scss,
+        });
+
+        let cache = self.cache_dir.as_ref().map(|cache_dir| {
+            CompileCache::new(
+                cache_dir,
+                &style_sheet,
+                syntax,
+                &self.settings,
+                &self.base_dir,
+                &self.pinned_class_names,
+            )
+        });
+
+        if let Some(cached_style_sheet) = cache.as_ref().and_then(|cache| cache.get(&style_sheet)) {
+            return Ok(cached_style_sheet);
+        }
+
+        let hash = hashing::hash_style_sheet(&style_sheet)?;
+        let css_compilation::CompiledCss {
+            mut css,
+            mut dependencies,
+            missing_files,
+        } = css_compilation::compile_style_sheet(
+            &style_sheet,
+            syntax,
+            &self.settings,
+            &self.base_dir,
+        )?;
+
+        if self.settings.bundle_css_imports && css.contains("@import") {
+            let (bundled_css, imported_files) = bundling::bundle_css_imports(
+                &css,
+                &style_sheet,
+                &self.base_dir,
+                self.settings.canonicalized_load_paths(&self.base_dir)?,
+                &self.settings,
+            )?;
+            css = bundled_css;
+            dependencies.extend(imported_files);
+        }
+
+        let mut assets = Vec::new();
+        if let Some(ref asset_settings) = self.settings.assets {
+            let (rewritten_css, referenced_assets, asset_files) = assets::process_asset_urls(
+                &css,
+                &style_sheet,
+                &self.base_dir,
+                &self.settings.canonicalized_load_paths(&self.base_dir)?,
+                asset_settings,
+                self.settings.parser_options(),
+            )?;
+            css = rewritten_css;
+            assets = referenced_assets;
+            dependencies.extend(asset_files);
+        }
+
+        dependencies.sort();
+        dependencies.dedup();
+
+        // grass has no source maps, the map points to the CSS compiled from the style sheet, e.g. `style.scss.css`
+        let source_name = self.settings.source_maps.then(|| {
+            format!(
+                "{}.css",
+                file_output::manifest_key(&style_sheet, &self.base_dir)
+            )
+        });
+        let (style_sheet_css, class_names, source_map) = transformer::transform_stylesheet(
+            &css,
+            &hash,
+            self.settings.clone(),
+            self.pinned_class_names.clone(),
+            source_name.as_deref(),
+        )?;
+
+        let compiled_style_sheet = CompiledStyleSheet {
+            css: style_sheet_css,
+            hash,
+            class_names,
+            original_style_sheet: style_sheet,
+            dependencies,
+            assets,
+            compressed: Vec::new(),
+            source_map,
+        };
+
+        if let Some(cache) = cache {
+            cache.store(&compiled_style_sheet, &missing_files)?;
+        }
+
+        Ok(compiled_style_sheet)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::Compiler;
+    use crate::{test_utils::fixture, Settings, StyleSheetKind, StyleSheetOptions, Syntax};
+
+    #[test]
+    fn compile_inline_style_sheet() {
+        let settings = Settings::builder()
+            .class_name_template("<original_name>")
+            .build()
+            .unwrap();
+        let fixture = fixture(&[]);
+        let compiler = Compiler::new(settings, fixture.path());
+
+        let compiled_style_sheet = compiler
+            .compile(StyleSheetKind::Inline(String::from(
+                ".test { .inner { color: red; } }",
+            )))
+            .unwrap();
+
+        assert_eq!(compiled_style_sheet.css, ".test .inner{color:red}");
+        assert_eq!(
+            compiled_style_sheet.class_names.get("inner").unwrap(),
+            "inner"
+        );
+    }
+
+    #[test]
+    fn missing_style_sheet_file_is_returned_as_error() {
+        let fixture = fixture(&[]);
+        let compiler = Compiler::new(Settings::default(), fixture.path());
+
+        let result = compiler.compile(StyleSheetKind::File(PathBuf::from(
+            "turf-this-file-does-not-exist.scss",
+        )));
+
+        assert!(matches!(result, Err(crate::Error::PathResolution(_))));
+    }
+
+    #[test]
+    fn cached_style_sheet_is_recompiled_when_an_import_changes() {
+        let fixture = fixture(&[
+            ("_colors.scss", "$color: red;"),
+            (
+                "style.scss",
+                "@use 'colors'; .test { color: colors.$color; }",
+            ),
+        ]);
+        let dir = fixture.path();
+
+        let settings = Settings::builder()
+            .class_name_template("<original_name>")
+            .build()
+            .unwrap();
+        let compiler = Compiler::new(settings, dir).with_cache_dir(dir.join("cache"));
+        let compile = || {
+            compiler
+                .compile(StyleSheetKind::File(PathBuf::from("style.scss")))
+                .unwrap()
+                .css
+        };
+
+        assert_eq!(compile(), ".test{color:red}");
+        assert_eq!(std::fs::read_dir(dir.join("cache")).unwrap().count(), 1);
+        assert_eq!(compile(), ".test{color:red}");
+
+        std::fs::write(dir.join("_colors.scss"), "$color: blue;").unwrap();
+        assert_eq!(compile(), ".test{color:#00f}");
+    }
+
+    #[test]
+    fn cached_style_sheet_is_recompiled_when_a_new_partial_shadows_an_import() {
+        let fixture = fixture(&[
+            ("vendor/_colors.scss", "$color: red;"),
+            (
+                "styles/style.scss",
+                "@use 'colors'; .test { color: colors.$color; }",
+            ),
+        ]);
+        let dir = fixture.path();
+
+        let settings = Settings::builder()
+            .class_name_template("<original_name>")
+            .load_path("vendor")
+            .build()
+            .unwrap();
+        let compiler = Compiler::new(settings, dir).with_cache_dir(dir.join("cache"));
+        let compile = || {
+            compiler
+                .compile(StyleSheetKind::File(PathBuf::from("styles/style.scss")))
+                .unwrap()
+                .css
+        };
+
+        assert_eq!(compile(), ".test{color:red}");
+        assert_eq!(compile(), ".test{color:red}");
+
+        // imports relative to the style sheet take precedence over the load paths
+        std::fs::write(dir.join("styles/_colors.scss"), "$color: blue;").unwrap();
+        assert_eq!(compile(), ".test{color:#00f}");
+    }
+
+    #[test]
+    fn dependencies_contain_exactly_the_imported_files() {
+        let fixture = fixture(&[
+            ("_colors.scss", "$color: red;"),
+            ("_unused.scss", "$color: blue;"),
+            (
+                "style.scss",
+                "@use 'colors'; .test { color: colors.$color; }",
+            ),
+        ]);
+        let dir = fixture.path();
+
+        let compiled_style_sheet = Compiler::new(Settings::default(), dir)
+            .compile(StyleSheetKind::File(PathBuf::from("style.scss")))
+            .unwrap();
+
+        let dir = dir.canonicalize().unwrap();
+        assert_eq!(
+            compiled_style_sheet.dependencies,
+            vec![dir.join("_colors.scss"), dir.join("style.scss")]
+        );
+    }
+
+    #[test]
+    fn classes_of_global_sources_are_not_renamed() {
+        let fixture = fixture(&[
+            (
+                "vendor/_library.scss",
+                ".library { color: red; } .btn { margin: 0; }",
+            ),
+            (
+                "style.scss",
+                "@use 'vendor/library'; .component { color: blue; } .btn { padding: 0; }",
+            ),
+        ]);
+        let dir = fixture.path();
+
+        let settings = Settings::builder()
+            .class_name_template("renamed-<original_name>")
+            .global_source("vendor/**")
+            .build()
+            .unwrap();
+        let compiled_style_sheet = Compiler::new(settings, dir)
+            .compile(StyleSheetKind::File(PathBuf::from("style.scss")))
+            .unwrap();
+
+        assert_eq!(
+            compiled_style_sheet.css,
+            ".library{color:red}.btn{margin:0}.renamed-component{color:#00f}.renamed-btn{padding:0}"
+        );
+        assert_eq!(compiled_style_sheet.class_names["library"], "library");
+        assert_eq!(compiled_style_sheet.class_names["btn"], "renamed-btn");
+    }
+
+    #[test]
+    fn local_css_imports_are_bundled_with_their_conditions() {
+        let fixture = fixture(&[
+            ("reset.css", ".reset { margin: 0; }"),
+            ("shared/print.css", ".print { color: red; }"),
+            (
+                "style.scss",
+                "@import 'https://example.com/font.css';\n\
+                 @import 'reset.css' layer(base);\n\
+                 @import 'print.css' print;\n\
+                 .test { color: blue; }",
+            ),
+        ]);
+        let dir = fixture.path();
+
+        let settings = Settings::builder()
+            .class_name_template("<original_name>")
+            .load_path("shared")
+            .bundle_css_imports(true)
+            .build()
+            .unwrap();
+        let compiled_style_sheet = Compiler::new(settings, dir)
+            .compile(StyleSheetKind::File(PathBuf::from("style.scss")))
+            .unwrap();
+
+        assert_eq!(
+            compiled_style_sheet.css,
+            "@import \"https://example.com/font.css\";\
+             @layer base{.reset{margin:0}}\
+             @media print{.print{color:red}}\
+             .test{color:#00f}"
+        );
+
+        let dir = dir.canonicalize().unwrap();
+        assert_eq!(
+            compiled_style_sheet.dependencies,
+            vec![
+                dir.join("reset.css"),
+                dir.join("shared/print.css"),
+                dir.join("style.scss")
+            ]
+        );
+    }
+
+    #[test]
+    fn asset_urls_are_inlined_or_rewritten_to_hashed_file_names() {
+        let fixture = fixture(&[
+            ("img/dot.svg", "<svg/>"),
+            (
+                "style.scss",
+                ".dot { background: url(img/dot.svg); }\n\
+                 .photo { background: url('img/photo.jpg'); }\n\
+                 .remote { background: url(https://example.com/remote.png); }",
+            ),
+        ]);
+        let dir = fixture.path();
+        std::fs::write(dir.join("img/photo.jpg"), [0u8; 64]).unwrap();
+
+        let settings = Settings::builder()
+            .class_name_template("<original_name>")
+            .asset_inline_limit(32)
+            .asset_url_prefix("/assets/")
+            .build()
+            .unwrap();
+        let compiled_style_sheet = Compiler::new(settings, dir)
+            .compile(StyleSheetKind::File(PathBuf::from("style.scss")))
+            .unwrap();
+
+        let dir = dir.canonicalize().unwrap();
+        let photo_file_name = format!("photo.{:016x}.jpg", xxhash_rust::xxh3::xxh3_64(&[0u8; 64]));
+        assert_eq!(
+            compiled_style_sheet.css,
+            format!(
+                ".dot{{background:url(data:image/svg+xml;base64,PHN2Zy8+)}}\
+                 .photo{{background:url(/assets/{photo_file_name})}}\
+                 .remote{{background:url(https://example.com/remote.png)}}"
+            )
+        );
+        assert_eq!(
+            compiled_style_sheet.assets,
+            vec![crate::Asset {
+                source: dir.join("img/photo.jpg"),
+                file_name: photo_file_name,
+            }]
+        );
+        assert_eq!(
+            compiled_style_sheet.dependencies,
+            vec![
+                dir.join("img/dot.svg"),
+                dir.join("img/photo.jpg"),
+                dir.join("style.scss")
+            ]
+        );
+    }
+
+    #[test]
+    fn drafts_and_features_are_passed_to_lightningcss() {
+        let settings = Settings::builder()
+            .class_name_template("<original_name>")
+            .draft("custom_media")
+            .include_feature("nesting")
+            .build()
+            .unwrap();
+        let options = StyleSheetOptions {
+            syntax: Some(Syntax::Css),
+            ..Default::default()
+        };
+
+        let fixture = fixture(&[]);
+        let compiled_style_sheet = Compiler::new(settings, fixture.path())
+            .compile_with_options(
+                StyleSheetKind::Inline(String::from(
+                    "@custom-media --small (max-width: 30em);\n\
+                     @media (--small) { .test { .inner { color: red; } } }",
+                )),
+                &options,
+            )
+            .unwrap();
+
+        assert_eq!(
+            compiled_style_sheet.css,
+            "@media (width<=30em){.test .inner{color:red}}"
+        );
+    }
+}
@@ -0,0 +1,78 @@
+use std::io::Write;
+
+use serde::Deserialize;
+
+/// A format the CSS output is pre-compressed with, for servers that serve compressed siblings of static files
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Compression {
+    Gzip,
+    Brotli,
+}
+
+impl Compression {
+    pub const ALL: [Self; 2] = [Self::Gzip, Self::Brotli];
+
+    /// The extension appended to the file name of the compressed sibling, e.g. `style.css.gz`
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Gzip => "gz",
+            Self::Brotli => "br",
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Brotli => "brotli",
+        }
+    }
+
+    /// Compresses the content with the best compression level, the output is only written once per change of the CSS
+    pub fn compress(&self, content: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+                // writing into a vector can't fail
+                encoder.write_all(content).unwrap();
+                encoder.finish().unwrap()
+            }
+            Self::Brotli => {
+                let mut compressed = Vec::new();
+                {
+                    let mut encoder = brotli::CompressorWriter::new(&mut compressed, 4096, 11, 22);
+                    encoder.write_all(content).unwrap();
+                }
+                compressed
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::Compression;
+
+    #[test]
+    fn compressed_content_decompresses_to_the_original() {
+        let css = ".class-a { color: red; }\n".repeat(20);
+
+        let mut gzip_decompressed = String::new();
+        flate2::read::GzDecoder::new(Compression::Gzip.compress(css.as_bytes()).as_slice())
+            .read_to_string(&mut gzip_decompressed)
+            .unwrap();
+        assert_eq!(gzip_decompressed, css);
+
+        let mut brotli_decompressed = String::new();
+        brotli::Decompressor::new(
+            Compression::Brotli.compress(css.as_bytes()).as_slice(),
+            4096,
+        )
+        .read_to_string(&mut brotli_decompressed)
+        .unwrap();
+        assert_eq!(brotli_decompressed, css);
+    }
+}
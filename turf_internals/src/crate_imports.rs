@@ -0,0 +1,548 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+};
+
+pub(crate) static CRATE_URL_SCHEME: &str = "crate:";
+
+#[derive(Debug, thiserror::Error)]
+pub enum CrateImportError {
+    #[error("error reading '{0}' - {1}")]
+    Read(PathBuf, std::io::Error),
+    #[error("error parsing '{0}' - {1}")]
+    Toml(PathBuf, toml::de::Error),
+    #[error("could not find the Cargo.lock of '{0}', build the crate once to create it")]
+    MissingLockFile(PathBuf),
+    #[error("could not find the Cargo home, set the CARGO_HOME environment variable")]
+    MissingCargoHome,
+    #[error("'{0}' is not pinned in the Cargo.lock, build the crate once to update it")]
+    NotLocked(String),
+    #[error("the sources of '{0}' were not found in the Cargo home, build the crate once to download them - vendored sources are not supported")]
+    NotDownloaded(String),
+    #[error(
+        "'{0}' is a dependency from '{1}', only path, registry and git dependencies are supported"
+    )]
+    UnsupportedSource(String, String),
+    #[error("'{0}' is not a dependency of the importing crate")]
+    UnknownCrate(String),
+    #[error("crate '{0}' does not export any style sheets, it needs to declare `exports` in its `[package.metadata.turf]`")]
+    NoExports(String),
+}
+
+/// The exported style sheet directories of the dependencies of a crate, by the crate names used in `crate:` URLs
+type CrateExports = HashMap<String, Vec<PathBuf>>;
+
+/// Resolves paths containing a `crate:<name>` component to the exported directories of the dependency `<name>`
+///
+/// Dependencies are located without running Cargo. Path dependencies are found through the `Cargo.toml` of the importing package, registry and git dependencies by the version or commit pinned in the `Cargo.lock` in the sources Cargo extracted to the Cargo home. Dependencies from other sources, e.g. vendored sources, are not supported.
+#[derive(Debug)]
+pub struct CrateResolver {
+    base_dir: PathBuf,
+    cargo_home: Option<PathBuf>,
+    exports: RefCell<CrateExports>,
+}
+
+/// A dependency as declared in the `Cargo.toml` of the importing package
+struct DeclaredDependency {
+    package: String,
+    path: Option<PathBuf>,
+}
+
+impl CrateResolver {
+    pub fn new(base_dir: &Path) -> Self {
+        let cargo_home = std::env::var_os("CARGO_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::home_dir().map(|home_dir| home_dir.join(".cargo")));
+
+        Self {
+            base_dir: base_dir.to_path_buf(),
+            cargo_home,
+            exports: Default::default(),
+        }
+    }
+
+    /// Returns the real path of a path containing a `crate:` URL, or `None` for any other path
+    pub fn resolve(&self, path: &Path) -> Result<Option<PathBuf>, CrateImportError> {
+        let Some((crate_name, rest)) = split_crate_url(path) else {
+            return Ok(None);
+        };
+
+        let export_dirs = self.export_dirs(&crate_name)?;
+        if export_dirs.is_empty() {
+            return Err(CrateImportError::NoExports(crate_name));
+        }
+
+        // grass probes several file names per import, the first export containing the file wins
+        let resolved_path = export_dirs
+            .iter()
+            .map(|export_dir| export_dir.join(&rest))
+            .find(|path| path.exists())
+            .unwrap_or_else(|| export_dirs[0].join(&rest));
+
+        Ok(Some(resolved_path))
+    }
+
+    fn export_dirs(&self, crate_name: &str) -> Result<Vec<PathBuf>, CrateImportError> {
+        let crate_name = crate_name.replace('-', "_");
+        if let Some(export_dirs) = self.exports.borrow().get(&crate_name) {
+            return Ok(export_dirs.clone());
+        }
+
+        let dependency = self
+            .declared_dependency(&crate_name)?
+            .ok_or_else(|| CrateImportError::UnknownCrate(crate_name.clone()))?;
+
+        let export_dirs = read_exports(&self.dependency_dir(&dependency)?)?;
+
+        self.exports
+            .borrow_mut()
+            .insert(crate_name, export_dirs.clone());
+
+        Ok(export_dirs)
+    }
+
+    /// Finds the dependency imported by the crate name in the dependency tables of the `Cargo.toml`
+    fn declared_dependency(
+        &self,
+        crate_name: &str,
+    ) -> Result<Option<DeclaredDependency>, CrateImportError> {
+        let manifest = read_toml(&self.base_dir.join("Cargo.toml"))?;
+
+        let declaration = dependency_tables(&manifest)
+            .flat_map(|table| table.iter())
+            .find(|(name, _)| name.replace('-', "_") == crate_name);
+        let Some((name, declaration)) = declaration else {
+            return Ok(None);
+        };
+
+        if declaration.get("workspace").and_then(toml::Value::as_bool) != Some(true) {
+            return Ok(Some(DeclaredDependency::new(
+                name,
+                declaration,
+                &self.base_dir,
+            )));
+        }
+
+        // inherited from the `[workspace.dependencies]` of the workspace root
+        for dir in self.base_dir.ancestors() {
+            let manifest_path = dir.join("Cargo.toml");
+            if !manifest_path.is_file() {
+                continue;
+            }
+
+            let manifest = read_toml(&manifest_path)?;
+            if let Some(workspace) = manifest.get("workspace") {
+                let declaration = workspace
+                    .get("dependencies")
+                    .and_then(|dependencies| dependencies.get(name));
+
+                return Ok(
+                    declaration.map(|declaration| DeclaredDependency::new(name, declaration, dir))
+                );
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// The directory of a path dependency or of a registry or git dependency extracted by Cargo
+    fn dependency_dir(&self, dependency: &DeclaredDependency) -> Result<PathBuf, CrateImportError> {
+        if let Some(ref path) = dependency.path {
+            return Ok(path.clone());
+        }
+
+        let lock_path = cargo_lock_file(&self.base_dir)
+            .ok_or_else(|| CrateImportError::MissingLockFile(self.base_dir.clone()))?;
+        let cargo_home = self
+            .cargo_home
+            .as_ref()
+            .ok_or(CrateImportError::MissingCargoHome)?;
+        let manifest = read_toml(&self.base_dir.join("Cargo.toml"))?;
+        let lock_file = read_toml(&lock_path)?;
+
+        let package_name = manifest
+            .get("package")
+            .and_then(|package| string_field(package, "name"));
+        let locked_packages = lock_file
+            .get("package")
+            .and_then(toml::Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        // the dependencies of the importing package, either `name` or `name version (source)`
+        let locked_dependency = locked_packages
+            .iter()
+            .find(|package| {
+                string_field(package, "name") == package_name && package.get("source").is_none()
+            })
+            .and_then(|package| package.get("dependencies"))
+            .and_then(toml::Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(toml::Value::as_str)
+            .map(|locked_dependency| locked_dependency.split(' ').collect::<Vec<_>>())
+            .find(|locked_dependency| locked_dependency[0] == dependency.package);
+        let not_locked = || CrateImportError::NotLocked(dependency.package.clone());
+        let locked_dependency = locked_dependency.ok_or_else(not_locked)?;
+
+        let locked_package = locked_packages.iter().find(|package| {
+            string_field(package, "name") == Some(dependency.package.as_str())
+                && locked_dependency
+                    .get(1)
+                    .is_none_or(|version| string_field(package, "version") == Some(*version))
+        });
+        let (version, source) = locked_package
+            .and_then(|package| {
+                Some((
+                    string_field(package, "version")?,
+                    string_field(package, "source")?,
+                ))
+            })
+            .ok_or_else(not_locked)?;
+        let package_id = format!("{}-{version}", dependency.package);
+
+        let package_dir = if source.starts_with("registry+") || source.starts_with("sparse+") {
+            registry_package_dir(cargo_home, &package_id)
+        } else if let Some(repository) = source.strip_prefix("git+") {
+            // the source ends with the locked commit, e.g. `git+https://…?branch=main#<commit>`
+            let commit = repository
+                .rsplit_once('#')
+                .map(|(_, commit)| commit)
+                .unwrap_or_default();
+            git_package_dir(cargo_home, commit, &dependency.package)
+        } else {
+            return Err(CrateImportError::UnsupportedSource(
+                dependency.package.clone(),
+                source.to_owned(),
+            ));
+        };
+
+        package_dir.ok_or(CrateImportError::NotDownloaded(package_id))
+    }
+}
+
+impl DeclaredDependency {
+    fn new(name: &str, declaration: &toml::Value, manifest_dir: &Path) -> Self {
+        Self {
+            package: declaration
+                .get("package")
+                .and_then(toml::Value::as_str)
+                .unwrap_or(name)
+                .to_owned(),
+            path: declaration
+                .get("path")
+                .and_then(toml::Value::as_str)
+                .map(|path| manifest_dir.join(path)),
+        }
+    }
+}
+
+/// The dependency tables of a manifest, including the ones of specific targets
+fn dependency_tables(manifest: &toml::Table) -> impl Iterator<Item = &toml::Table> {
+    static DEPENDENCY_KEYS: [&str; 3] = ["dependencies", "dev-dependencies", "build-dependencies"];
+
+    let target_manifests = manifest
+        .get("target")
+        .and_then(toml::Value::as_table)
+        .into_iter()
+        .flat_map(|targets| targets.values().filter_map(toml::Value::as_table));
+
+    std::iter::once(manifest)
+        .chain(target_manifests)
+        .flat_map(|manifest| {
+            DEPENDENCY_KEYS
+                .iter()
+                .filter_map(|key| manifest.get(*key).and_then(toml::Value::as_table))
+        })
+}
+
+fn string_field<'a>(value: &'a toml::Value, key: &str) -> Option<&'a str> {
+    value.get(key).and_then(toml::Value::as_str)
+}
+
+fn read_toml(path: &Path) -> Result<toml::Table, CrateImportError> {
+    let content =
+        std::fs::read_to_string(path).map_err(|e| CrateImportError::Read(path.to_path_buf(), e))?;
+
+    content
+        .parse()
+        .map_err(|e| CrateImportError::Toml(path.to_path_buf(), e))
+}
+
+/// The directories declared as `exports` in the `[package.metadata.turf]` of a dependency
+fn read_exports(package_dir: &Path) -> Result<Vec<PathBuf>, CrateImportError> {
+    let manifest = read_toml(&package_dir.join("Cargo.toml"))?;
+
+    Ok(manifest
+        .get("package")
+        .and_then(|package| package.get("metadata"))
+        .and_then(|metadata| metadata.get("turf"))
+        .and_then(|turf| turf.get("exports"))
+        .and_then(toml::Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(toml::Value::as_str)
+        .map(|export_dir| package_dir.join(export_dir))
+        .collect())
+}
+
+/// Every registry has a directory of its own, the extracted package is looked up in all of them
+fn registry_package_dir(cargo_home: &Path, package_id: &str) -> Option<PathBuf> {
+    std::fs::read_dir(cargo_home.join("registry").join("src"))
+        .ok()?
+        .filter_map(|registry| registry.ok())
+        .map(|registry| registry.path().join(package_id))
+        .find(|package_dir| package_dir.join("Cargo.toml").is_file())
+}
+
+/// Cargo checks out every commit of a repository into a directory named after the abbreviated commit hash, the package can be anywhere in the repository
+fn git_package_dir(cargo_home: &Path, commit: &str, package: &str) -> Option<PathBuf> {
+    let checkouts = std::fs::read_dir(cargo_home.join("git").join("checkouts")).ok()?;
+
+    checkouts
+        .filter_map(|repository| repository.ok())
+        .filter_map(|repository| std::fs::read_dir(repository.path()).ok())
+        .flatten()
+        .filter_map(|checkout| checkout.ok())
+        .filter(|checkout| {
+            let name = checkout.file_name();
+            let name = name.to_string_lossy();
+            name.len() >= 7 && commit.starts_with(name.as_ref())
+        })
+        .find_map(|checkout| find_package(&checkout.path(), package))
+}
+
+/// Searches the directory of a repository for the manifest of a package
+fn find_package(dir: &Path, package: &str) -> Option<PathBuf> {
+    let manifest_path = dir.join("Cargo.toml");
+    let is_package = read_toml(&manifest_path)
+        .ok()
+        .and_then(|manifest| {
+            manifest
+                .get("package")
+                .and_then(|manifest_package| string_field(manifest_package, "name"))
+                .map(|name| name == package)
+        })
+        .unwrap_or_default();
+    if is_package {
+        return Some(dir.to_path_buf());
+    }
+
+    std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+        .filter(|entry| !matches!(entry.file_name().to_str(), Some(".git" | "target")))
+        .find_map(|entry| find_package(&entry.path(), package))
+}
+
+/// The lock file pinning the versions of the imported crates
+///
+/// It is tracked together with the imported files, so a cached style sheet is recompiled when a dependency is updated.
+pub fn cargo_lock_file(base_dir: &Path) -> Option<PathBuf> {
+    base_dir
+        .ancestors()
+        .map(|dir| dir.join("Cargo.lock"))
+        .find(|path| path.is_file())
+        .and_then(|path| path.canonicalize().ok())
+}
+
+fn split_crate_url(path: &Path) -> Option<(String, PathBuf)> {
+    let mut components = path.components();
+
+    let crate_name = components.by_ref().find_map(|component| match component {
+        Component::Normal(name) => name.to_str()?.strip_prefix(CRATE_URL_SCHEME),
+        _ => None,
+    })?;
+
+    Some((crate_name.to_owned(), components.collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{split_crate_url, CrateImportError, CrateResolver};
+    use crate::test_utils::fixture;
+
+    #[test]
+    fn split_crate_url_from_import_path() {
+        assert_eq!(
+            split_crate_url(Path::new(
+                "/project/styles/crate:design_system/tokens/_colors.scss"
+            )),
+            Some((
+                String::from("design_system"),
+                PathBuf::from("tokens/_colors.scss")
+            ))
+        );
+        assert_eq!(
+            split_crate_url(Path::new("/project/styles/_colors.scss")),
+            None
+        );
+    }
+
+    #[test]
+    fn dependencies_are_located_without_cargo() {
+        let fixture = fixture(&[
+            (
+                "Cargo.toml",
+                "[workspace]\n\
+                 members = [\"app\"]\n\
+                 [workspace.dependencies]\n\
+                 inherited = { path = \"inherited\" }",
+            ),
+            (
+                "Cargo.lock",
+                "version = 3\n\
+                 [[package]]\n\
+                 name = \"app\"\n\
+                 version = \"0.1.0\"\n\
+                 dependencies = [\"design-tokens 2.1.0\", \"git-styles\", \"inherited\", \"local-styles\"]\n\
+                 [[package]]\n\
+                 name = \"design-tokens\"\n\
+                 version = \"1.0.0\"\n\
+                 source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\
+                 [[package]]\n\
+                 name = \"design-tokens\"\n\
+                 version = \"2.1.0\"\n\
+                 source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\
+                 [[package]]\n\
+                 name = \"git-styles\"\n\
+                 version = \"0.3.0\"\n\
+                 source = \"git+https://example.com/styles.git?branch=main#4f2a9c1d8e7b6a5f4e3d2c1b0a9f8e7d6c5b4a3f\"",
+            ),
+            (
+                "app/Cargo.toml",
+                "[package]\n\
+                 name = \"app\"\n\
+                 version = \"0.1.0\"\n\
+                 [dependencies]\n\
+                 local-styles = { path = \"../local_styles\" }\n\
+                 tokens = { package = \"design-tokens\", version = \"2\" }\n\
+                 git-styles = { git = \"https://example.com/styles.git\", branch = \"main\" }\n\
+                 [target.'cfg(unix)'.dev-dependencies]\n\
+                 inherited = { workspace = true }",
+            ),
+            (
+                "local_styles/Cargo.toml",
+                "[package.metadata.turf]\nexports = [\"styles\"]",
+            ),
+            (
+                "inherited/Cargo.toml",
+                "[package.metadata.turf]\nexports = [\"first\", \"second\"]",
+            ),
+            ("inherited/second/_mixins.scss", ""),
+            (
+                "cargo_home/registry/src/index.crates.io-0/design-tokens-1.0.0/Cargo.toml",
+                "[package.metadata.turf]\nexports = [\"old\"]",
+            ),
+            (
+                "cargo_home/registry/src/index.crates.io-0/design-tokens-2.1.0/Cargo.toml",
+                "[package.metadata.turf]\nexports = [\"scss\"]",
+            ),
+            (
+                "cargo_home/git/checkouts/styles-0/0a1b2c3/styles/Cargo.toml",
+                "[package]\nname = \"git-styles\"\n[package.metadata.turf]\nexports = [\"old\"]",
+            ),
+            (
+                "cargo_home/git/checkouts/styles-0/4f2a9c1/Cargo.toml",
+                "[workspace]\nmembers = [\"crates/*\"]",
+            ),
+            (
+                "cargo_home/git/checkouts/styles-0/4f2a9c1/target/package/Cargo.toml",
+                "[package]\nname = \"git-styles\"\n[package.metadata.turf]\nexports = [\"packaged\"]",
+            ),
+            (
+                "cargo_home/git/checkouts/styles-0/4f2a9c1/crates/styles/Cargo.toml",
+                "[package]\nname = \"git-styles\"\n[package.metadata.turf]\nexports = [\"scss\"]",
+            ),
+        ]);
+        let dir = fixture.path();
+
+        let resolver = CrateResolver {
+            cargo_home: Some(dir.join("cargo_home")),
+            ..CrateResolver::new(&dir.join("app"))
+        };
+        let resolve = |path: &str| resolver.resolve(Path::new(path));
+
+        assert_eq!(
+            resolve("/app/styles/crate:tokens/_colors.scss").unwrap(),
+            Some(dir.join(
+                "cargo_home/registry/src/index.crates.io-0/design-tokens-2.1.0/scss/_colors.scss"
+            ))
+        );
+        assert_eq!(
+            resolve("/app/styles/crate:git_styles/_colors.scss").unwrap(),
+            Some(
+                dir.join(
+                    "cargo_home/git/checkouts/styles-0/4f2a9c1/crates/styles/scss/_colors.scss"
+                )
+            )
+        );
+        assert_eq!(
+            resolve("/app/styles/crate:local_styles/button").unwrap(),
+            Some(dir.join("app/../local_styles/styles/button"))
+        );
+        assert_eq!(
+            resolve("/app/styles/crate:inherited/_mixins.scss").unwrap(),
+            Some(dir.join("inherited/second/_mixins.scss"))
+        );
+        assert!(matches!(
+            resolve("/app/styles/crate:design_system/_colors.scss"),
+            Err(CrateImportError::UnknownCrate(_))
+        ));
+        assert_eq!(resolve("/app/styles/_colors.scss").unwrap(), None);
+    }
+
+    #[test]
+    fn dependencies_that_cannot_be_located_are_reported() {
+        let fixture = fixture(&[
+            (
+                "Cargo.toml",
+                "[package]\n\
+                 name = \"app\"\n\
+                 [dependencies]\n\
+                 vendored = \"1\"\n\
+                 unlocked = \"1\"\n\
+                 alternative = \"1\"",
+            ),
+            (
+                "Cargo.lock",
+                "version = 3\n\
+                 [[package]]\n\
+                 name = \"app\"\n\
+                 version = \"0.1.0\"\n\
+                 dependencies = [\"alternative\", \"vendored\"]\n\
+                 [[package]]\n\
+                 name = \"alternative\"\n\
+                 version = \"1.0.0\"\n\
+                 source = \"directory+/vendor\"\n\
+                 [[package]]\n\
+                 name = \"vendored\"\n\
+                 version = \"1.2.0\"\n\
+                 source = \"registry+https://github.com/rust-lang/crates.io-index\"",
+            ),
+        ]);
+        let dir = fixture.path();
+
+        let resolver = CrateResolver {
+            cargo_home: Some(dir.join("cargo_home")),
+            ..CrateResolver::new(dir)
+        };
+        let resolve = |path: &str| resolver.resolve(Path::new(path));
+
+        assert!(matches!(
+            resolve("/styles/crate:vendored/_colors.scss"),
+            Err(CrateImportError::NotDownloaded(package_id)) if package_id == "vendored-1.2.0"
+        ));
+        assert!(matches!(
+            resolve("/styles/crate:unlocked/_colors.scss"),
+            Err(CrateImportError::NotLocked(package)) if package == "unlocked"
+        ));
+        assert!(matches!(
+            resolve("/styles/crate:alternative/_colors.scss"),
+            Err(CrateImportError::UnsupportedSource(package, _)) if package == "alternative"
+        ));
+    }
+}
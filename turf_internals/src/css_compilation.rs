@@ -1,6 +1,17 @@
-use std::path::{Path, PathBuf};
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
 
-use crate::{path_utils, Settings, StyleSheetKind};
+use crate::{
+    crate_imports::{cargo_lock_file, CrateImportError, CrateResolver},
+    package_imports::{PackageImportError, PackageResolver},
+    path_utils,
+    settings::GlobalSources,
+    transformer::{GLOBAL_SOURCE_END_RULE, GLOBAL_SOURCE_START_RULE},
+    Settings, StyleSheetKind, Syntax,
+};
 
 #[derive(thiserror::Error, Debug)]
 pub enum CssCompilationError {
@@ -8,8 +19,14 @@ pub enum CssCompilationError {
     File(Box<grass::Error>, PathBuf),
     #[error("error compiling inline scss '{0}'")]
     Inline(#[from] Box<grass::Error>),
+    #[error("error reading css file '{0}' - {1}")]
+    CssFile(PathBuf, std::io::Error),
     #[error(transparent)]
     PathResolutionError(#[from] path_utils::PathResolutionError),
+    #[error("error resolving crate import - {0}")]
+    CrateImport(#[from] CrateImportError),
+    #[error("error resolving package import - {0}")]
+    PackageImport(#[from] PackageImportError),
 }
 
 impl<P> From<(Box<grass::Error>, P)> for CssCompilationError
@@ -30,15 +47,183 @@ where
     }
 }
 
+static GLOBAL_SOURCE_START_COMMENT: &str = "/* turf:global-source-start */";
+static GLOBAL_SOURCE_END_COMMENT: &str = "/* turf:global-source-end */";
+
+/// A file system for grass that resolves `crate:` and `~` URLs and records every file read during compilation
+///
+/// The candidates grass probed for an import but didn't find are recorded as well, a file created at one of them later shadows the imported file.
+///
+/// The content of global sources is enclosed in marker comments, which grass keeps in place of the CSS generated by the file.
+#[derive(Debug)]
+struct TrackingFs {
+    base_dir: PathBuf,
+    global_sources: GlobalSources,
+    crate_resolver: CrateResolver,
+    package_resolver: PackageResolver,
+    import_error: RefCell<Option<CssCompilationError>>,
+    imports_crates: Cell<bool>,
+    read_files: RefCell<BTreeSet<PathBuf>>,
+    missing_files: RefCell<BTreeSet<PathBuf>>,
+}
+
+impl TrackingFs {
+    fn new(base_dir: &Path, package_dirs: Vec<PathBuf>, global_sources: GlobalSources) -> Self {
+        Self {
+            base_dir: base_dir.to_path_buf(),
+            global_sources,
+            crate_resolver: CrateResolver::new(base_dir),
+            package_resolver: PackageResolver::new(package_dirs),
+            import_error: RefCell::new(None),
+            imports_crates: Cell::new(false),
+            read_files: RefCell::new(BTreeSet::new()),
+            missing_files: RefCell::new(BTreeSet::new()),
+        }
+    }
+
+    /// Maps `crate:` and `~` URLs to their real path, the first resolution error is kept to be reported after compilation
+    fn resolve(&self, path: &Path) -> PathBuf {
+        match self.crate_resolver.resolve(path) {
+            Ok(Some(resolved_path)) => {
+                self.imports_crates.set(true);
+                return resolved_path;
+            }
+            Ok(None) => {}
+            Err(error) => {
+                self.import_error.borrow_mut().get_or_insert(error.into());
+                return path.to_path_buf();
+            }
+        }
+
+        match self.package_resolver.resolve(path) {
+            Ok(Some(package_import)) => {
+                // the entry file of a package depends on its package.json
+                self.read_files
+                    .borrow_mut()
+                    .extend(package_import.package_json);
+                package_import.path
+            }
+            Ok(None) => path.to_path_buf(),
+            Err(error) => {
+                self.import_error.borrow_mut().get_or_insert(error.into());
+                path.to_path_buf()
+            }
+        }
+    }
+}
+
+impl grass::Fs for TrackingFs {
+    fn is_dir(&self, path: &Path) -> bool {
+        self.resolve(path).is_dir()
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        let path = self.resolve(path);
+        let is_file = path.is_file();
+        if !is_file {
+            self.missing_files.borrow_mut().insert(path);
+        }
+
+        is_file
+    }
+
+    fn read(&self, path: &Path) -> std::io::Result<Vec<u8>> {
+        let path = self.resolve(path);
+        let mut content = std::fs::read(&path)?;
+        let path = std::fs::canonicalize(&path).unwrap_or(path);
+
+        if !self.global_sources.is_empty() && self.global_sources.is_match(&self.base_dir, &path) {
+            content = [
+                GLOBAL_SOURCE_START_COMMENT.as_bytes(),
+                b"\n",
+                &content,
+                b"\n",
+                GLOBAL_SOURCE_END_COMMENT.as_bytes(),
+                b"\n",
+            ]
+            .concat();
+        }
+
+        self.read_files.borrow_mut().insert(path);
+
+        Ok(content)
+    }
+
+    fn canonicalize(&self, path: &Path) -> std::io::Result<PathBuf> {
+        std::fs::canonicalize(self.resolve(path))
+    }
+}
+
+/// The CSS of a style sheet compiled by grass
+pub struct CompiledCss {
+    pub css: String,
+    /// The files read during compilation
+    pub dependencies: Vec<PathBuf>,
+    /// The import candidates that didn't exist during compilation
+    pub missing_files: Vec<PathBuf>,
+}
+
+/// Compiles the style sheet and returns the CSS together with all files that were read during compilation
+///
+/// Plain CSS is returned as is, without being compiled by grass.
 pub fn compile_style_sheet(
     style_sheet: &StyleSheetKind,
+    syntax: Syntax,
     settings: &Settings,
-) -> Result<String, CssCompilationError> {
-    Ok(match style_sheet {
-        StyleSheetKind::File(ref path) => grass::from_path(path, &settings.clone().try_into()?)
-            .map_err(|e| CssCompilationError::from((e, path.clone())))?,
-        StyleSheetKind::Inline(ref style_sheet) => {
-            grass::from_string(style_sheet, &settings.clone().try_into()?)?
+    base_dir: &Path,
+) -> Result<CompiledCss, CssCompilationError> {
+    let fs = TrackingFs::new(
+        base_dir,
+        settings.canonicalized_package_dirs(base_dir)?,
+        settings.global_sources.clone(),
+    );
+    let options = settings.grass_options(base_dir)?.fs(&fs);
+    let options = match syntax {
+        Syntax::Scss | Syntax::Css => options.input_syntax(grass::InputSyntax::Scss),
+        Syntax::Sass => options.input_syntax(grass::InputSyntax::Sass),
+    };
+
+    let result = match (style_sheet, syntax) {
+        (StyleSheetKind::File(ref path), Syntax::Css) => grass::Fs::read(&fs, path)
+            .and_then(|content| {
+                String::from_utf8(content)
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+            })
+            .map_err(|e| CssCompilationError::CssFile(path.clone(), e)),
+        (StyleSheetKind::Inline(ref style_sheet), Syntax::Css) => Ok(style_sheet.clone()),
+        (StyleSheetKind::File(ref path), _) => grass::from_path(path, &options)
+            .map_err(|e| CssCompilationError::from((e, path.clone()))),
+        (StyleSheetKind::Inline(ref style_sheet), _) => {
+            grass::from_string(style_sheet, &options).map_err(Into::into)
         }
+    };
+
+    // grass only reports a missing file, the reason an import could not be resolved is more helpful
+    let css = match (result, fs.import_error.take()) {
+        (Ok(css), _) => css,
+        (Err(_), Some(import_error)) => return Err(import_error),
+        (Err(error), None) => return Err(error),
+    };
+
+    // lightningcss drops comments, so the markers are turned into at-rules that are removed during transformation
+    let css = css
+        .replace(
+            GLOBAL_SOURCE_START_COMMENT,
+            &format!("@{GLOBAL_SOURCE_START_RULE};"),
+        )
+        .replace(
+            GLOBAL_SOURCE_END_COMMENT,
+            &format!("@{GLOBAL_SOURCE_END_RULE};"),
+        );
+
+    let mut dependencies = fs.read_files.into_inner();
+    if fs.imports_crates.get() {
+        dependencies.extend(cargo_lock_file(base_dir));
+    }
+
+    Ok(CompiledCss {
+        css,
+        dependencies: dependencies.into_iter().collect(),
+        missing_files: fs.missing_files.into_inner().into_iter().collect(),
     })
 }
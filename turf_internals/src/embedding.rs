@@ -0,0 +1,84 @@
+//! Embedding of the compiled CSS into HTML documents
+
+use base64::Engine;
+use sha2::{Digest, Sha256, Sha384};
+
+static STYLE_END_TAG: &[u8] = b"</style";
+
+/// The hash of the CSS in the format of a Content-Security-Policy source, e.g. `'sha256-…'`
+///
+/// A `style-src` directive listing the hash allows a `<style>` element containing exactly the CSS.
+pub fn csp_hash(css: &str) -> String {
+    let digest = Sha256::digest(css.as_bytes());
+    format!(
+        "'sha256-{}'",
+        base64::engine::general_purpose::STANDARD.encode(digest)
+    )
+}
+
+/// The Subresource Integrity hash of a CSS file, e.g. `sha384-…`, for the `integrity` attribute of the `<link>` referencing the file
+pub fn integrity_hash(content: &[u8]) -> String {
+    let digest = Sha384::digest(content);
+    format!(
+        "sha384-{}",
+        base64::engine::general_purpose::STANDARD.encode(digest)
+    )
+}
+
+/// Escapes every `</style`, so the CSS can't end the `<style>` element it is embedded in
+///
+/// The sequence can only occur in strings and URLs, where `\/` is a valid escape of the slash.
+pub(crate) fn escape_style_end_tags(css: &str) -> String {
+    let mut escaped_css = String::with_capacity(css.len());
+    let mut start = 0;
+
+    for index in style_end_tags(css) {
+        escaped_css.push_str(&css[start..=index]);
+        escaped_css.push('\\');
+        start = index + 1;
+    }
+    escaped_css.push_str(&css[start..]);
+
+    escaped_css
+}
+
+/// The byte positions of every `</style` in the CSS regardless of its case, in ascending order
+pub(crate) fn style_end_tags(css: &str) -> Vec<usize> {
+    css.match_indices("</")
+        .map(|(index, _)| index)
+        .filter(|index| {
+            css.as_bytes()[*index..]
+                .get(..STYLE_END_TAG.len())
+                .is_some_and(|tag| tag.eq_ignore_ascii_case(STYLE_END_TAG))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{csp_hash, escape_style_end_tags, integrity_hash};
+
+    #[test]
+    fn style_end_tags_are_escaped_regardless_of_their_case() {
+        assert_eq!(
+            escape_style_end_tags(r#".a::after{content:"</style></STYLE></div>"}"#),
+            r#".a::after{content:"<\/style><\/STYLE></div>"}"#
+        );
+    }
+
+    #[test]
+    fn csp_hash_is_the_base64_encoded_sha256_of_the_css() {
+        assert_eq!(
+            csp_hash(""),
+            "'sha256-47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU='"
+        );
+    }
+
+    #[test]
+    fn integrity_hash_is_the_base64_encoded_sha384_of_the_content() {
+        assert_eq!(
+            integrity_hash(b""),
+            "sha384-OLBgp1GsljhM2TJ+sbHjaiH9txEUvgdDTAzHv2P24donTt6/529l+9Ua0vFImLlb"
+        );
+    }
+}
@@ -1,106 +1,1292 @@
 use std::{
-    fs::{create_dir_all, File},
-    io::Write,
-    path::PathBuf,
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
 };
 
-use crate::{settings::FileOutput, StyleSheetKind};
+use serde::{Deserialize, Serialize};
 
-static DIRS_RESET: std::sync::OnceLock<()> = std::sync::OnceLock::new();
+use crate::{
+    compression::Compression,
+    embedding::integrity_hash,
+    media_split::SplitCss,
+    settings::{cargo_target_dir, FileOutput, SeparateFileNames},
+    source_maps, Asset, CompiledStyleSheet, StyleSheetKind, StyleSheetOptions,
+};
+
+static MANIFEST_FILE_NAME: &str = "turf-manifest.json";
+static GLOBAL_MANIFEST_FILE_NAME: &str = "turf-global-manifest.json";
+/// Replaced with a hash of the content in the file name of the global CSS file
+pub static HASH_PLACEHOLDER: &str = "<hash>";
+static FRAGMENTS_DIR_NAME: &str = "turf-fragments";
+static LOCK_FILE_NAME: &str = ".lock";
+
+/// Identifies the compilation of a crate by the current process
+///
+/// Every fragment is written with the generation of its crate. A crate's macros are all expanded by a single process, so fragments of an older generation belong to style sheets that were removed from the crate.
+static GENERATION: std::sync::OnceLock<String> = std::sync::OnceLock::new();
 
 #[derive(Debug, thiserror::Error)]
 #[error("error writing css file '{0}' - {1}")]
 pub struct CssFileWriteError(PathBuf, std::io::Error);
 
-fn reset_file_output(output_paths: &FileOutput) -> Result<(), CssFileWriteError> {
-    if let Some(path) = &output_paths.global_css_file_path {
-        if let Err(error) = std::fs::remove_file(path) {
-            match error.kind() {
-                std::io::ErrorKind::NotFound => {}
-                _ => Err(CssFileWriteError(path.clone(), error))?,
+/// The output of a single style sheet, from which the output files are reassembled
+#[derive(Debug, Serialize, Deserialize)]
+struct Fragment {
+    crate_key: String,
+    /// The directory of the package the crate belongs to, the `source` is relative to it
+    package_dir: PathBuf,
+    generation: String,
+    /// The key of the style sheet in the manifest
+    source: String,
+    #[serde(default)]
+    bundle: Option<String>,
+    #[serde(default)]
+    priority: i32,
+    separate_file: PathBuf,
+    css: String,
+    /// The CSS without the blocks split off by media, if the output is split
+    #[serde(default)]
+    main_css: Option<String>,
+    #[serde(default)]
+    media_css: BTreeMap<String, String>,
+    class_names: BTreeMap<String, String>,
+    assets: Vec<Asset>,
+    #[serde(default)]
+    source_map: Option<String>,
+}
+
+/// A separate CSS file as described in the `turf-manifest.json`
+#[derive(Debug, Serialize, Deserialize)]
+struct ManifestEntry {
+    file: PathBuf,
+    hash: String,
+    #[serde(default)]
+    bundle: Option<String>,
+    /// The Subresource Integrity hash of the file
+    #[serde(default)]
+    integrity: String,
+    size: usize,
+    class_names: BTreeMap<String, String>,
+}
+
+/// The global CSS file or a bundle as described in the `turf-global-manifest.json` next to it
+#[derive(Debug, Serialize)]
+struct GlobalManifestEntry<'a> {
+    /// The name of the bundle, the global CSS file has none
+    bundle: Option<&'a str>,
+    /// The media query of a file split off by media, for the `media` attribute of its `<link>`
+    media: Option<&'a str>,
+    /// The final file name, with the `<hash>` placeholder resolved
+    file: PathBuf,
+    integrity: String,
+    size: usize,
+    /// The keys of the style sheets in the file, as in the `turf-manifest.json`
+    style_sheets: Vec<&'a str>,
+}
+
+/// The path of the separate CSS file relative to the separate files directory
+fn separate_file_path(
+    style: &str,
+    style_sheet: &StyleSheetKind,
+    file_names: SeparateFileNames,
+    base_dir: &Path,
+) -> PathBuf {
+    let css_hash = xxhash_rust::xxh3::xxh3_64(style.as_bytes());
+
+    match (style_sheet, file_names) {
+        (StyleSheetKind::File(path), SeparateFileNames::Mirrored) => {
+            // style sheets outside of the base directory have no relative path to mirror
+            match relative_to_base_dir(path, base_dir) {
+                Some(relative_path) => relative_path.with_extension("css"),
+                None => hashed_file_name(path, css_hash),
             }
-        };
+        }
+        (StyleSheetKind::File(path), SeparateFileNames::Hashed) => hashed_file_name(path, css_hash),
+        (StyleSheetKind::Inline(style_sheet), SeparateFileNames::Mirrored) => {
+            let hash = xxhash_rust::xxh3::xxh3_64(style_sheet.as_bytes());
+            PathBuf::from(format!("{hash:016x}.css"))
+        }
+        (StyleSheetKind::Inline(_), SeparateFileNames::Hashed) => {
+            PathBuf::from(format!("{css_hash:016x}.css"))
+        }
+    }
+}
+
+fn relative_to_base_dir(path: &Path, base_dir: &Path) -> Option<PathBuf> {
+    let base_dir = base_dir.canonicalize().ok()?;
+    path.strip_prefix(base_dir).ok().map(Path::to_path_buf)
+}
 
-        create_dir_all(path.parent().expect("global css file path has parent dir"))
-            .map_err(|error| CssFileWriteError(path.clone(), error))?;
+fn hashed_file_name(path: &Path, css_hash: u64) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("style");
+    PathBuf::from(format!("{stem}.{css_hash:016x}.css"))
+}
+
+/// The key of the style sheet in the manifests, its path relative to the base directory or `inline:<hash>`
+pub(crate) fn manifest_key(style_sheet: &StyleSheetKind, base_dir: &Path) -> String {
+    match style_sheet {
+        StyleSheetKind::File(path) => relative_to_base_dir(path, base_dir)
+            .unwrap_or_else(|| path.clone())
+            .display()
+            .to_string(),
+        StyleSheetKind::Inline(style_sheet) => {
+            let hash = xxhash_rust::xxh3::xxh3_64(style_sheet.as_bytes());
+            format!("inline:{hash:x?}")
+        }
     }
-    if let Some(path) = &output_paths.separate_css_files_path {
-        if let Err(error) = std::fs::remove_dir_all(path) {
-            match error.kind() {
-                std::io::ErrorKind::NotFound => {}
-                _ => Err(CssFileWriteError(path.clone(), error))?,
-            }
-        };
+}
+
+fn write_error(path: &Path) -> impl FnOnce(std::io::Error) -> CssFileWriteError + '_ {
+    move |error| CssFileWriteError(path.to_path_buf(), error)
+}
 
-        create_dir_all(path).map_err(|error| CssFileWriteError(path.clone(), error))?;
+/// Writes the file through a temporary file, so readers never see a partially written file, and skips unchanged files
+fn write_file(path: &Path, content: &[u8]) -> Result<(), CssFileWriteError> {
+    if std::fs::read(path).is_ok_and(|existing_content| existing_content == content) {
+        return Ok(());
     }
 
-    Ok(())
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        std::fs::create_dir_all(parent).map_err(write_error(path))?;
+    }
+
+    let temporary_path = path.with_extension(format!("{}.tmp", std::process::id()));
+    std::fs::write(&temporary_path, content).map_err(write_error(path))?;
+    std::fs::rename(&temporary_path, path).map_err(write_error(path))
 }
 
-fn append_to_separate_file(
-    style: &str,
-    mut separate_files_dir: PathBuf,
-    style_sheet: &StyleSheetKind,
+/// The path of the sibling of a CSS file compressed with the given format, e.g. `style.css.gz`
+fn compressed_path(path: &Path, compression: Compression) -> PathBuf {
+    let mut compressed_path = path.as_os_str().to_owned();
+    compressed_path.push(".");
+    compressed_path.push(compression.extension());
+    PathBuf::from(compressed_path)
+}
+
+/// Writes a CSS file together with its compressed siblings, siblings of formats that are no longer configured are removed
+///
+/// The CSS is only compressed again if it changed or a sibling is missing.
+fn write_css_with_siblings(
+    path: &Path,
+    css: &str,
+    compress: &[Compression],
 ) -> Result<(), CssFileWriteError> {
-    match style_sheet {
-        StyleSheetKind::File(path) => {
-            separate_files_dir.push(path.file_name().expect("current css file exists"));
-            separate_files_dir.set_extension("css");
+    let unchanged =
+        std::fs::read(path).is_ok_and(|existing_content| existing_content == css.as_bytes());
+    write_file(path, css.as_bytes())?;
+
+    for compression in Compression::ALL {
+        let sibling_path = compressed_path(path, compression);
+
+        if !compress.contains(&compression) {
+            remove_file(&sibling_path)?;
+        } else if !unchanged || !sibling_path.exists() {
+            write_file(&sibling_path, &compression.compress(css.as_bytes()))?;
         }
-        StyleSheetKind::Inline(style_sheet) => {
-            let hash = xxhash_rust::xxh3::xxh3_64(style_sheet.as_bytes());
-            separate_files_dir.push(&format!("{hash:x?}.css"));
+    }
+
+    Ok(())
+}
+
+/// Writes a CSS file together with its compressed siblings and its source map, e.g. `style.css.map`, and returns the written CSS
+///
+/// The CSS is followed by a `sourceMappingURL` comment referencing the source map. Without a source map, the source map of an earlier CSS is removed.
+fn write_css_with_source_map(
+    path: &Path,
+    css: &str,
+    source_map: Option<&str>,
+    compress: &[Compression],
+) -> Result<String, CssFileWriteError> {
+    let source_map_path = source_maps::source_map_path(path);
+    let css = match source_map {
+        Some(source_map) => {
+            write_file(&source_map_path, source_map.as_bytes())?;
+            let file_name = source_map_path
+                .file_name()
+                .map(|file_name| file_name.to_string_lossy())
+                .unwrap_or_default();
+            format!(
+                "{css}{}",
+                source_maps::source_mapping_url_comment(&file_name)
+            )
+        }
+        None => {
+            remove_file(&source_map_path)?;
+            css.to_owned()
         }
     };
 
-    let mut output_file = File::options()
-        .create(true)
-        .append(true)
-        .open(&separate_files_dir)
-        .map_err(|error| CssFileWriteError(separate_files_dir.clone(), error))?;
+    write_css_with_siblings(path, &css, compress)?;
+    Ok(css)
+}
+
+/// Removes a CSS file together with its compressed siblings and its source map
+fn remove_css_file(path: &Path) -> Result<(), CssFileWriteError> {
+    remove_file(path)?;
+    remove_file(&source_maps::source_map_path(path))?;
 
-    output_file
-        .write_all(style.as_bytes())
-        .map_err(|error| CssFileWriteError(separate_files_dir, error))?;
+    for compression in Compression::ALL {
+        remove_file(&compressed_path(path, compression))?;
+    }
 
     Ok(())
 }
 
-fn append_to_global_file(style: &str, global_file_path: &PathBuf) -> Result<(), CssFileWriteError> {
-    let mut global_css_file = File::options()
-        .create(true)
-        .append(true)
-        .open(global_file_path)
-        .map_err(|error| CssFileWriteError(global_file_path.clone(), error))?;
+/// The path of the file split off by media, the name of the media is inserted in front of the extension, e.g. `global.print.css`
+fn media_file_path(path: &Path, media_name: &str) -> PathBuf {
+    match (path.file_stem(), path.extension()) {
+        (Some(stem), Some(extension)) => {
+            let mut file_name = stem.to_owned();
+            file_name.push(format!(".{media_name}."));
+            file_name.push(extension);
+            path.with_file_name(file_name)
+        }
+        _ => {
+            let mut path = path.as_os_str().to_owned();
+            path.push(format!(".{media_name}"));
+            PathBuf::from(path)
+        }
+    }
+}
+
+/// Writes a CSS file and its siblings compressed with the given formats, a `<hash>` in the file name is replaced with a hash of the CSS and the returned path is the path that was written
+///
+/// Files written for an earlier CSS, which only differ in their hash, are removed together with their compressed siblings.
+pub fn write_css_file(
+    path: &Path,
+    css: &str,
+    compress: &[Compression],
+) -> Result<PathBuf, CssFileWriteError> {
+    write_css_file_with_source_map(path, css, None, compress).map(|(path, _)| path)
+}
 
-    global_css_file
-        .write_all(style.as_bytes())
-        .map_err(|error| CssFileWriteError(global_file_path.clone(), error))?;
+/// Writes the CSS of several style sheets into a single CSS file like [`write_css_file`] and returns the written path and CSS
+///
+/// If every style sheet has a source map, their combined source map is written next to the file and referenced at its end.
+pub fn write_combined_css_file(
+    path: &Path,
+    style_sheets: &[&CompiledStyleSheet],
+    compress: &[Compression],
+) -> Result<(PathBuf, String), CssFileWriteError> {
+    let parts = style_sheets
+        .iter()
+        .map(|style_sheet| (style_sheet.css.as_str(), style_sheet.source_map.as_deref()))
+        .collect::<Vec<_>>();
+    let (css, source_map) = combine_css(path, &parts)?;
 
-    Ok(())
+    write_css_file_with_source_map(path, &css, source_map.as_deref(), compress)
+}
+
+/// Concatenates the CSS of several style sheets, which is only combined with a source map if every style sheet has one
+///
+/// With source maps, every style sheet starts on a new line.
+fn combine_css(
+    path: &Path,
+    parts: &[(&str, Option<&str>)],
+) -> Result<(String, Option<String>), CssFileWriteError> {
+    let source_maps = parts
+        .iter()
+        .map(|(css, source_map)| Some((*css, (*source_map)?)))
+        .collect::<Option<Vec<_>>>()
+        .filter(|source_maps| !source_maps.is_empty());
+
+    match source_maps {
+        Some(source_maps) => source_maps::concatenate(&source_maps)
+            .map(|(css, source_map)| (css, Some(source_map)))
+            .map_err(|e| CssFileWriteError(path.to_path_buf(), std::io::Error::other(e))),
+        None => Ok((parts.iter().map(|(css, _)| *css).collect(), None)),
+    }
+}
+
+/// Writes a CSS file like [`write_css_file`] together with its source map and returns the written path and CSS
+///
+/// The hash of the file name is computed from the CSS without the `sourceMappingURL` comment, which references the source map of the hashed file.
+fn write_css_file_with_source_map(
+    path: &Path,
+    css: &str,
+    source_map: Option<&str>,
+    compress: &[Compression],
+) -> Result<(PathBuf, String), CssFileWriteError> {
+    let Some((prefix, suffix)) = path
+        .file_name()
+        .and_then(|file_name| file_name.to_str())
+        .and_then(|file_name| file_name.split_once(HASH_PLACEHOLDER))
+    else {
+        let css = write_css_with_source_map(path, css, source_map, compress)?;
+        return Ok((path.to_path_buf(), css));
+    };
+
+    let hash = format!("{:016x}", xxhash_rust::xxh3::xxh3_64(css.as_bytes()));
+    let hashed_path = path.with_file_name(format!("{prefix}{hash}{suffix}"));
+    let css = write_css_with_source_map(&hashed_path, css, source_map, compress)?;
+
+    let dir = match hashed_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    for entry in std::fs::read_dir(dir).map_err(write_error(dir))? {
+        let entry_path = entry.map_err(write_error(dir))?.path();
+        let is_earlier_output = entry_path
+            .file_name()
+            .and_then(|file_name| file_name.to_str())
+            .map(|file_name| {
+                Compression::ALL
+                    .iter()
+                    .map(Compression::extension)
+                    .chain(["map"])
+                    .find_map(|extension| file_name.strip_suffix(extension)?.strip_suffix('.'))
+                    .unwrap_or(file_name)
+            })
+            .and_then(|file_name| file_name.strip_prefix(prefix)?.strip_suffix(suffix))
+            .is_some_and(|other_hash| {
+                other_hash != hash
+                    && other_hash.len() == hash.len()
+                    && other_hash.bytes().all(|byte| byte.is_ascii_hexdigit())
+            });
+
+        if is_earlier_output {
+            remove_file(&entry_path)?;
+        }
+    }
+
+    Ok((hashed_path, css))
+}
+
+fn remove_file(path: &Path) -> Result<(), CssFileWriteError> {
+    match std::fs::remove_file(path) {
+        Err(error) if error.kind() != std::io::ErrorKind::NotFound => {
+            Err(CssFileWriteError(path.to_path_buf(), error))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The arguments of the compiler the proc macros run in, with `@path` argument files expanded
+///
+/// Cargo passes the arguments through an argument file with one argument per line when the command line gets too long.
+fn compiler_args() -> Vec<String> {
+    std::env::args()
+        .skip(1)
+        .flat_map(|arg| match arg.strip_prefix('@') {
+            Some(path) => std::fs::read_to_string(path)
+                .map(|content| content.lines().map(str::to_owned).collect())
+                .unwrap_or_default(),
+            None => vec![arg],
+        })
+        .collect()
 }
 
+/// Only builds producing code write output files, `cargo check`, `cargo doc` and rust-analyzer could be using different settings
+///
+/// The kind of build is guessed from the compiler's command line, builds invoking rustc through a wrapper with other arguments are treated as output runs.
+fn is_output_run() -> bool {
+    if std::env::var_os("RUST_ANALYZER_INTERNALS_DO_NOT_USE").is_some() {
+        return false;
+    }
+
+    let is_rustdoc = std::env::args_os()
+        .next()
+        .and_then(|program| {
+            Path::new(&program)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().starts_with("rustdoc"))
+        })
+        .unwrap_or_default();
+    if is_rustdoc {
+        return false;
+    }
+
+    // proc macros run inside of rustc, `cargo check` only emits metadata
+    let mut args = compiler_args().into_iter();
+    while let Some(arg) = args.next() {
+        let emit = match arg.strip_prefix("--emit") {
+            Some("") => args.next().unwrap_or_default(),
+            Some(emit) => emit.trim_start_matches('=').to_owned(),
+            None => continue,
+        };
+
+        return emit.split(',').any(|kind| kind.starts_with("link"));
+    }
+
+    true
+}
+
+/// The fragments of all crates writing to the same output files share a directory
+fn fragments_dir(output_paths: &FileOutput, base_dir: &Path) -> PathBuf {
+    let output_key = format!(
+        "{:?}\0{:?}\0{:?}\0{:?}",
+        output_paths
+            .global_css_file_path
+            .as_deref()
+            .map(std::path::absolute),
+        output_paths
+            .bundles
+            .values()
+            .map(|path| std::path::absolute(path).ok())
+            .collect::<Vec<_>>(),
+        output_paths
+            .separate_css_files_path
+            .as_deref()
+            .map(std::path::absolute),
+        output_paths.assets_path.as_deref().map(std::path::absolute),
+    );
+
+    cargo_target_dir(base_dir)
+        .join(FRAGMENTS_DIR_NAME)
+        .join(format!(
+            "{:016x}",
+            xxhash_rust::xxh3::xxh3_64(output_key.as_bytes())
+        ))
+}
+
+/// Identifies the crate that is compiled, the library, binaries and tests of a package are separate crates
+///
+/// Cargo sets the crate name for every crate and the binary name for binaries, only unit tests are told apart by the `--test` argument. Without Cargo, the crate root in the arguments is used instead of the crate name.
+fn crate_key(base_dir: &Path) -> String {
+    let args = compiler_args();
+    let crate_name = std::env::var("CARGO_CRATE_NAME").ok().unwrap_or_else(|| {
+        args.iter()
+            .find(|arg| arg.ends_with(".rs"))
+            .cloned()
+            .unwrap_or_default()
+    });
+    let is_bin = std::env::var_os("CARGO_BIN_NAME").is_some();
+    let is_test = args.iter().any(|arg| arg == "--test");
+
+    format!("{}#{crate_name}#{is_bin}#{is_test}", base_dir.display())
+}
+
+/// Serializes the output of concurrently compiled crates, released by removing the lock file on drop
+struct OutputLock(PathBuf);
+
+impl OutputLock {
+    /// A lock file older than this is left behind by a process that was killed while holding it
+    const STALE_AFTER: std::time::Duration = std::time::Duration::from_secs(30);
+
+    fn acquire(path: PathBuf) -> Result<Self, CssFileWriteError> {
+        loop {
+            match std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(_) => return Ok(Self(path)),
+                Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => {
+                    let is_stale = std::fs::metadata(&path)
+                        .and_then(|metadata| metadata.modified())
+                        .ok()
+                        .and_then(|modified| modified.elapsed().ok())
+                        .is_some_and(|age| age > Self::STALE_AFTER);
+                    if is_stale {
+                        remove_file(&path)?;
+                    } else {
+                        std::thread::sleep(std::time::Duration::from_millis(10));
+                    }
+                }
+                Err(error) => return Err(CssFileWriteError(path, error)),
+            }
+        }
+    }
+}
+
+impl Drop for OutputLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+fn crate_generation_path(fragments_dir: &Path, crate_key: &str) -> PathBuf {
+    fragments_dir.join(format!(
+        "{:016x}.generation",
+        xxhash_rust::xxh3::xxh3_64(crate_key.as_bytes())
+    ))
+}
+
+/// Stores the output of the style sheet as fragment and reassembles all output files from the fragments
 pub fn perform_css_file_output(
     output_paths: FileOutput,
-    style: &str,
-    style_sheet_kind: &StyleSheetKind,
+    compiled_style_sheet: &CompiledStyleSheet,
+    split_css: Option<SplitCss>,
+    options: &StyleSheetOptions,
+    base_dir: &Path,
 ) -> Result<(), CssFileWriteError> {
-    if DIRS_RESET.get().is_none() {
-        reset_file_output(&output_paths)?;
+    if !is_output_run() {
+        return Ok(());
+    }
+
+    let fragments_dir = fragments_dir(&output_paths, base_dir);
+    std::fs::create_dir_all(&fragments_dir).map_err(write_error(&fragments_dir))?;
 
-        DIRS_RESET
-            .set(())
-            .expect("internal turf state has already been set, but should be empty");
+    let _lock = OutputLock::acquire(fragments_dir.join(LOCK_FILE_NAME))?;
+
+    let crate_key = crate_key(base_dir);
+    let generation = GENERATION.get_or_init(|| {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        format!("{:x}-{nanos:x}", std::process::id())
+    });
+    // skipped if unchanged, the process may write to several fragment directories
+    write_file(
+        &crate_generation_path(&fragments_dir, &crate_key),
+        generation.as_bytes(),
+    )?;
+
+    let style = compiled_style_sheet.css.as_str();
+    let style_sheet_kind = &compiled_style_sheet.original_style_sheet;
+    let source = manifest_key(style_sheet_kind, base_dir);
+    let priority = options
+        .priority
+        .or_else(|| output_paths.order.priority(Path::new(&source)))
+        .unwrap_or_default();
+    let fragment = Fragment {
+        separate_file: separate_file_path(
+            style,
+            style_sheet_kind,
+            output_paths.separate_css_file_names,
+            base_dir,
+        ),
+        css: compiled_style_sheet.css.clone(),
+        main_css: split_css.as_ref().map(|split_css| split_css.main.clone()),
+        media_css: split_css
+            .map(|split_css| split_css.media)
+            .unwrap_or_default(),
+        class_names: compiled_style_sheet
+            .class_names
+            .iter()
+            .map(|(original, generated)| (original.clone(), generated.clone()))
+            .collect(),
+        assets: compiled_style_sheet.assets.clone(),
+        source_map: compiled_style_sheet.source_map.clone(),
+        bundle: options.bundle.clone(),
+        priority,
+        generation: generation.clone(),
+        crate_key: crate_key.clone(),
+        package_dir: base_dir.to_path_buf(),
+        source,
+    };
+    let fragment_path = fragments_dir.join(format!(
+        "{:016x}.json",
+        xxhash_rust::xxh3::xxh3_64(format!("{crate_key}\0{}", fragment.source).as_bytes())
+    ));
+    let content = serde_json::to_vec(&fragment)
+        .map_err(|e| CssFileWriteError(fragment_path.clone(), e.into()))?;
+    write_file(&fragment_path, &content)?;
+
+    let fragments = read_fragments(&fragments_dir)?;
+    assemble_output(&output_paths, &fragments)
+}
+
+/// Reads the fragments of the current generation of each crate, all other fragments are removed
+///
+/// A crate without style sheets never starts a new generation, so fragments of style sheet files that no longer exist are removed as well.
+fn read_fragments(fragments_dir: &Path) -> Result<Vec<Fragment>, CssFileWriteError> {
+    let entries = std::fs::read_dir(fragments_dir).map_err(write_error(fragments_dir))?;
+    let mut generations = BTreeMap::new();
+    let mut fragments = Vec::new();
+
+    for entry in entries {
+        let path = entry.map_err(write_error(fragments_dir))?.path();
+        if path.extension().and_then(|extension| extension.to_str()) != Some("json") {
+            continue;
+        }
+
+        let fragment = std::fs::read(&path)
+            .ok()
+            .and_then(|content| serde_json::from_slice::<Fragment>(&content).ok());
+        let Some(fragment) = fragment else {
+            remove_file(&path)?;
+            continue;
+        };
+
+        let current_generation = generations
+            .entry(fragment.crate_key.clone())
+            .or_insert_with(|| {
+                std::fs::read_to_string(crate_generation_path(fragments_dir, &fragment.crate_key))
+                    .ok()
+            });
+        let is_removed = match fragment.source.strip_prefix("inline:") {
+            Some(_) => !fragment.package_dir.exists(),
+            None => !fragment.package_dir.join(&fragment.source).exists(),
+        };
+        if is_removed || current_generation.as_deref() != Some(fragment.generation.as_str()) {
+            remove_file(&path)?;
+            continue;
+        }
+
+        fragments.push(fragment);
     }
 
-    if let Some(output_path) = output_paths.separate_css_files_path {
-        append_to_separate_file(style, output_path, style_sheet_kind)?;
+    // the order doesn't depend on the order the macros were expanded in
+    fragments.sort_by(|a, b| {
+        (&a.source, &a.package_dir, &a.crate_key).cmp(&(&b.source, &b.package_dir, &b.crate_key))
+    });
+    // a style sheet used by several crates of a package, e.g. a library and its tests, is only output once
+    fragments.dedup_by(|a, b| a.source == b.source && a.package_dir == b.package_dir);
+    // the sort is stable, so style sheets of the same priority stay ordered by their source
+    fragments.sort_by_key(|fragment| fragment.priority);
+
+    Ok(fragments)
+}
+
+fn assemble_output(
+    output_paths: &FileOutput,
+    fragments: &[Fragment],
+) -> Result<(), CssFileWriteError> {
+    let combined_files = output_paths
+        .global_css_file_path
+        .iter()
+        .map(|path| (None, path))
+        .chain(
+            output_paths
+                .bundles
+                .iter()
+                .map(|(bundle, path)| (Some(bundle.as_str()), path)),
+        );
+
+    // every combined file is accompanied by a file for each media of the split
+    let media_files = combined_files.flat_map(|(bundle, path)| {
+        std::iter::once((bundle, None, path.clone())).chain(output_paths.media_split.iter().map(
+            move |(name, query)| {
+                (
+                    bundle,
+                    Some((name.as_str(), query.as_str())),
+                    media_file_path(path, name),
+                )
+            },
+        ))
+    });
+
+    // files in the same directory share their manifest
+    let mut global_manifests: BTreeMap<PathBuf, Vec<GlobalManifestEntry>> = BTreeMap::new();
+    for (bundle, media, path) in media_files {
+        let file_fragments = fragments
+            .iter()
+            .filter(|fragment| fragment.bundle.as_deref() == bundle)
+            .filter_map(|fragment| {
+                let css = match media {
+                    None => fragment.main_css.as_deref().unwrap_or(&fragment.css),
+                    Some((name, _)) => fragment.media_css.get(name)?,
+                };
+                // the source maps only match the CSS if nothing was split off
+                let source_map = fragment
+                    .source_map
+                    .as_deref()
+                    .filter(|_| media.is_none() && fragment.main_css.is_none());
+                (media.is_none() || !css.is_empty())
+                    .then_some((fragment.source.as_str(), (css, source_map)))
+            })
+            .collect::<Vec<_>>();
+
+        let (css, source_map) = combine_css(
+            &path,
+            &file_fragments
+                .iter()
+                .map(|(_, part)| *part)
+                .collect::<Vec<_>>(),
+        )?;
+        let (file, css) = write_css_file_with_source_map(
+            &path,
+            &css,
+            source_map.as_deref(),
+            &output_paths.compress,
+        )?;
+
+        global_manifests
+            .entry(file.with_file_name(GLOBAL_MANIFEST_FILE_NAME))
+            .or_default()
+            .push(GlobalManifestEntry {
+                bundle,
+                media: media.map(|(_, query)| query),
+                file: file.file_name().map(PathBuf::from).unwrap_or_default(),
+                integrity: integrity_hash(css.as_bytes()),
+                size: css.len(),
+                style_sheets: file_fragments.iter().map(|(source, _)| *source).collect(),
+            });
+    }
+
+    for (manifest_path, manifest) in global_manifests {
+        let content = serde_json::to_vec_pretty(&manifest)
+            .map_err(|e| CssFileWriteError(manifest_path.clone(), e.into()))?;
+        write_file(&manifest_path, &content)?;
     }
 
-    if let Some(output_path) = output_paths.global_css_file_path {
-        append_to_global_file(style, &output_path)?;
+    if let Some(ref separate_files_dir) = output_paths.separate_css_files_path {
+        write_separate_files(separate_files_dir, fragments, &output_paths.compress)?;
+    }
+
+    if let Some(ref assets_path) = output_paths.assets_path {
+        copy_assets(assets_path, fragments)?;
     }
 
     Ok(())
 }
+
+/// Writes the separate files and the manifest, files of style sheets missing from the new manifest are removed
+fn write_separate_files(
+    separate_files_dir: &Path,
+    fragments: &[Fragment],
+    compress: &[Compression],
+) -> Result<(), CssFileWriteError> {
+    let manifest_path = separate_files_dir.join(MANIFEST_FILE_NAME);
+    let previous_manifest: BTreeMap<String, ManifestEntry> = std::fs::read(&manifest_path)
+        .ok()
+        .and_then(|content| serde_json::from_slice(&content).ok())
+        .unwrap_or_default();
+
+    let mut manifest = BTreeMap::new();
+    let mut sources_by_file = BTreeMap::new();
+    for fragment in fragments {
+        // e.g. `style.scss` and `style.sass`, or style sheets of the same name in different packages
+        if let Some(other_source) =
+            sources_by_file.insert(&fragment.separate_file, &fragment.source)
+        {
+            let path = separate_files_dir.join(&fragment.separate_file);
+            return Err(CssFileWriteError(
+                path,
+                std::io::Error::other(format!(
+                    "the style sheets '{other_source}' and '{}' are written to the same separate file, rename one of them or use `separate_css_file_names = \"hashed\"`",
+                    fragment.source
+                )),
+            ));
+        }
+
+        let css = write_css_with_source_map(
+            &separate_files_dir.join(&fragment.separate_file),
+            &fragment.css,
+            fragment.source_map.as_deref(),
+            compress,
+        )?;
+
+        manifest.insert(
+            fragment.source.clone(),
+            ManifestEntry {
+                file: fragment.separate_file.clone(),
+                hash: format!(
+                    "{:016x}",
+                    xxhash_rust::xxh3::xxh3_64(fragment.css.as_bytes())
+                ),
+                bundle: fragment.bundle.clone(),
+                integrity: integrity_hash(css.as_bytes()),
+                size: css.len(),
+                class_names: fragment.class_names.clone(),
+            },
+        );
+    }
+
+    let current_files = manifest
+        .values()
+        .map(|entry| &entry.file)
+        .collect::<BTreeSet<_>>();
+    for entry in previous_manifest.values() {
+        if !current_files.contains(&entry.file) {
+            remove_css_file(&separate_files_dir.join(&entry.file))?;
+        }
+    }
+
+    let content = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| CssFileWriteError(manifest_path.clone(), e.into()))?;
+    write_file(&manifest_path, &content)
+}
+
+/// Copies the assets of all fragments, assets that are no longer referenced are removed
+fn copy_assets(assets_dir: &Path, fragments: &[Fragment]) -> Result<(), CssFileWriteError> {
+    std::fs::create_dir_all(assets_dir).map_err(write_error(assets_dir))?;
+
+    let assets = fragments
+        .iter()
+        .flat_map(|fragment| &fragment.assets)
+        .map(|asset| (asset.file_name.as_str(), &asset.source))
+        .collect::<BTreeMap<_, _>>();
+
+    for (file_name, source) in &assets {
+        let target_path = assets_dir.join(file_name);
+
+        // the file name contains the hash of the content, so an existing file is up to date
+        if !target_path.exists() {
+            std::fs::copy(source, &target_path).map_err(write_error(&target_path))?;
+        }
+    }
+
+    for entry in std::fs::read_dir(assets_dir).map_err(write_error(assets_dir))? {
+        let path = entry.map_err(write_error(assets_dir))?.path();
+        let is_referenced = path
+            .file_name()
+            .and_then(|file_name| file_name.to_str())
+            .is_some_and(|file_name| assets.contains_key(file_name));
+
+        if path.is_file() && !is_referenced {
+            remove_file(&path)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{
+        compressed_path, crate_generation_path, crate_key, fragments_dir, media_file_path,
+        perform_css_file_output, separate_file_path, write_css_file, OutputLock,
+    };
+    use crate::{
+        compression::Compression,
+        settings::{FileOutput, SeparateFileNames},
+        test_utils::fixture,
+        CompiledStyleSheet, StyleSheetKind, StyleSheetOptions,
+    };
+
+    #[test]
+    fn separate_file_paths_of_style_sheets_with_the_same_name_do_not_collide() {
+        let fixture = fixture(&[]);
+        let base_dir = fixture.path().canonicalize().unwrap();
+        let path = |style_sheet: &str, file_names| {
+            separate_file_path(
+                ".a{color:red}",
+                &StyleSheetKind::File(base_dir.join(style_sheet)),
+                file_names,
+                &base_dir,
+            )
+        };
+
+        assert_eq!(
+            path("button/style.scss", SeparateFileNames::Mirrored),
+            PathBuf::from("button/style.css")
+        );
+        assert_eq!(
+            path("card/style.scss", SeparateFileNames::Mirrored),
+            PathBuf::from("card/style.css")
+        );
+
+        let hashed_path = path("button/style.scss", SeparateFileNames::Hashed);
+        assert_eq!(hashed_path.parent(), Some(Path::new("")));
+        assert!(hashed_path.to_str().unwrap().starts_with("style."));
+        assert_ne!(
+            hashed_path,
+            separate_file_path(
+                ".a{color:blue}",
+                &StyleSheetKind::File(base_dir.join("card/style.scss")),
+                SeparateFileNames::Hashed,
+                &base_dir,
+            )
+        );
+    }
+
+    #[test]
+    fn style_sheets_written_to_the_same_separate_file_are_rejected() {
+        let fixture = fixture(&[("Cargo.lock", ""), ("style.scss", ""), ("style.sass", "")]);
+        let dir = fixture.path().canonicalize().unwrap();
+
+        let output_paths = FileOutput {
+            separate_css_files_path: Some(dir.join("out")),
+            ..Default::default()
+        };
+        let output = |name: &str| {
+            let compiled_style_sheet = CompiledStyleSheet {
+                css: String::from(".a{}"),
+                hash: String::new(),
+                class_names: Default::default(),
+                original_style_sheet: StyleSheetKind::File(dir.join(name)),
+                dependencies: Vec::new(),
+                assets: Vec::new(),
+                compressed: Vec::new(),
+                source_map: None,
+            };
+            perform_css_file_output(
+                output_paths.clone(),
+                &compiled_style_sheet,
+                None,
+                &StyleSheetOptions::default(),
+                &dir,
+            )
+        };
+
+        output("style.scss").unwrap();
+        let error = output("style.sass").unwrap_err();
+        assert!(error.to_string().contains("'style.sass' and 'style.scss'"));
+    }
+
+    #[test]
+    fn output_is_reassembled_from_the_fragments_of_the_current_generation() {
+        let fixture = fixture(&[
+            ("Cargo.lock", ""),
+            ("a.scss", ""),
+            ("b.scss", ""),
+            ("admin.scss", ""),
+        ]);
+        let dir = fixture.path().canonicalize().unwrap();
+
+        let output_paths = FileOutput {
+            global_css_file_path: Some(dir.join("out/global.css")),
+            separate_css_files_path: Some(dir.join("out/separate")),
+            bundles: [(String::from("admin"), dir.join("out/admin.css"))].into(),
+            ..Default::default()
+        };
+        let output = |name: &str, css: &str, bundle: Option<&str>| {
+            let compiled_style_sheet = CompiledStyleSheet {
+                css: css.to_owned(),
+                hash: String::new(),
+                class_names: Default::default(),
+                original_style_sheet: StyleSheetKind::File(dir.join(name)),
+                dependencies: Vec::new(),
+                assets: Vec::new(),
+                compressed: Vec::new(),
+                source_map: None,
+            };
+            let options = StyleSheetOptions {
+                bundle: bundle.map(str::to_owned),
+                ..Default::default()
+            };
+            perform_css_file_output(
+                output_paths.clone(),
+                &compiled_style_sheet,
+                None,
+                &options,
+                &dir,
+            )
+            .unwrap();
+        };
+
+        output("b.scss", ".b{}", None);
+        output("admin.scss", ".admin{}", Some("admin"));
+        output("a.scss", ".a{}", None);
+        assert_eq!(
+            std::fs::read_to_string(dir.join("out/global.css")).unwrap(),
+            ".a{}.b{}"
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.join("out/admin.css")).unwrap(),
+            ".admin{}"
+        );
+
+        // a fragment of an earlier compilation of the crate, whose style sheet was removed since
+        let fragments_dir = fragments_dir(&output_paths, &dir);
+        let stale_fragment = serde_json::json!({
+            "crate_key": crate_key(&dir),
+            "package_dir": dir,
+            "generation": "earlier",
+            "source": "c.scss",
+            "separate_file": "c.css",
+            "css": ".c{}",
+            "class_names": {},
+            "assets": [],
+        });
+        std::fs::write(fragments_dir.join("stale.json"), stale_fragment.to_string()).unwrap();
+        // a fragment of another crate, which no longer compiles any style sheets, of a deleted file
+        let other_crate_key = "other#crate";
+        std::fs::write(
+            crate_generation_path(&fragments_dir, other_crate_key),
+            "current",
+        )
+        .unwrap();
+        let removed_fragment = serde_json::json!({
+            "crate_key": other_crate_key,
+            "package_dir": dir,
+            "generation": "current",
+            "source": "d.scss",
+            "separate_file": "d.css",
+            "css": ".d{}",
+            "class_names": {},
+            "assets": [],
+        });
+        std::fs::write(
+            fragments_dir.join("removed.json"),
+            removed_fragment.to_string(),
+        )
+        .unwrap();
+
+        output("a.scss", ".a{color:red}", None);
+        assert_eq!(
+            std::fs::read_to_string(dir.join("out/global.css")).unwrap(),
+            ".a{color:red}.b{}"
+        );
+        assert!(!fragments_dir.join("stale.json").exists());
+        assert!(!fragments_dir.join("removed.json").exists());
+        let global_manifest: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(dir.join("out/turf-global-manifest.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(global_manifest[0]["file"], "global.css");
+        assert_eq!(
+            global_manifest[0]["integrity"],
+            crate::integrity_hash(b".a{color:red}.b{}")
+        );
+        assert_eq!(global_manifest[1]["bundle"], "admin");
+        assert_eq!(
+            global_manifest[1]["style_sheets"],
+            serde_json::json!(["admin.scss"])
+        );
+        assert!(dir.join("out/separate/b.css").exists());
+        assert!(!dir.join("out/separate/c.css").exists());
+    }
+
+    #[test]
+    fn style_sheets_are_only_deduplicated_within_a_package() {
+        let fixture = fixture(&[
+            ("Cargo.lock", ""),
+            ("first/style.scss", ""),
+            ("second/style.scss", ""),
+        ]);
+        let dir = fixture.path().canonicalize().unwrap();
+
+        let output_paths = FileOutput {
+            global_css_file_path: Some(dir.join("global.css")),
+            ..Default::default()
+        };
+        let output = |package_dir: &Path, css: &str| {
+            let compiled_style_sheet = CompiledStyleSheet {
+                css: css.to_owned(),
+                hash: String::new(),
+                class_names: Default::default(),
+                original_style_sheet: StyleSheetKind::File(package_dir.join("style.scss")),
+                dependencies: Vec::new(),
+                assets: Vec::new(),
+                compressed: Vec::new(),
+                source_map: None,
+            };
+            perform_css_file_output(
+                output_paths.clone(),
+                &compiled_style_sheet,
+                None,
+                &StyleSheetOptions::default(),
+                package_dir,
+            )
+            .unwrap();
+        };
+
+        // the same style sheet compiled by another crate of the first package, e.g. its tests
+        let fragments_dir = fragments_dir(&output_paths, &dir);
+        std::fs::create_dir_all(&fragments_dir).unwrap();
+        let other_crate_key = format!("{}#tests/style.rs#true", dir.join("first").display());
+        std::fs::write(
+            crate_generation_path(&fragments_dir, &other_crate_key),
+            "other",
+        )
+        .unwrap();
+        let other_crate_fragment = serde_json::json!({
+            "crate_key": other_crate_key,
+            "package_dir": dir.join("first"),
+            "generation": "other",
+            "source": "style.scss",
+            "separate_file": "style.css",
+            "css": ".first{}",
+            "class_names": {},
+            "assets": [],
+        });
+        std::fs::write(
+            fragments_dir.join("other.json"),
+            other_crate_fragment.to_string(),
+        )
+        .unwrap();
+
+        output(&dir.join("first"), ".first{}");
+        output(&dir.join("second"), ".second{}");
+
+        assert_eq!(
+            std::fs::read_to_string(dir.join("global.css")).unwrap(),
+            ".first{}.second{}"
+        );
+    }
+
+    #[test]
+    fn hash_placeholder_is_resolved_and_earlier_files_are_pruned() {
+        let fixture = fixture(&[("app.unrelated.css", "")]);
+        let dir = fixture.path();
+
+        let first_path =
+            write_css_file(&dir.join("app.<hash>.css"), ".a{}", &[Compression::Gzip]).unwrap();
+        let file_name = first_path.file_name().unwrap().to_str().unwrap();
+        assert!(file_name.starts_with("app.") && file_name.ends_with(".css"));
+        assert_eq!(file_name.len(), "app.".len() + 16 + ".css".len());
+        assert_eq!(std::fs::read_to_string(&first_path).unwrap(), ".a{}");
+        assert!(compressed_path(&first_path, Compression::Gzip).exists());
+        assert!(!compressed_path(&first_path, Compression::Brotli).exists());
+
+        let second_path = write_css_file(&dir.join("app.<hash>.css"), ".b{}", &[]).unwrap();
+        assert_ne!(first_path, second_path);
+        assert!(second_path.exists());
+        assert!(!first_path.exists());
+        assert!(!compressed_path(&first_path, Compression::Gzip).exists());
+        assert!(dir.join("app.unrelated.css").exists());
+    }
+
+    #[test]
+    fn style_sheets_are_ordered_by_priority_and_then_by_source() {
+        let fixture = fixture(&[
+            ("Cargo.lock", ""),
+            ("a.scss", ""),
+            ("b.scss", ""),
+            ("z.scss", ""),
+            ("base/reset.scss", ""),
+            ("base/typography.scss", ""),
+        ]);
+        let dir = fixture.path().canonicalize().unwrap();
+
+        let output_paths: FileOutput = toml::from_str(&format!(
+            r#"
+            global_css_file_path = "{}"
+            order = [
+                {{ glob = "base/reset.scss", priority = -20 }},
+                {{ glob = "base/**", priority = -10 }},
+            ]
+            "#,
+            dir.join("global.css").display()
+        ))
+        .unwrap();
+        let output = |name: &str, priority: Option<i32>| {
+            let compiled_style_sheet = CompiledStyleSheet {
+                css: format!(".{}{{}}", name.replace(['/', '.'], "-")),
+                hash: String::new(),
+                class_names: Default::default(),
+                original_style_sheet: StyleSheetKind::File(dir.join(name)),
+                dependencies: Vec::new(),
+                assets: Vec::new(),
+                compressed: Vec::new(),
+                source_map: None,
+            };
+            let options = StyleSheetOptions {
+                priority,
+                ..Default::default()
+            };
+            perform_css_file_output(
+                output_paths.clone(),
+                &compiled_style_sheet,
+                None,
+                &options,
+                &dir,
+            )
+            .unwrap();
+        };
+
+        output("b.scss", None);
+        output("a.scss", None);
+        output("base/typography.scss", None);
+        output("base/reset.scss", None);
+        output("z.scss", Some(-30));
+
+        assert_eq!(
+            std::fs::read_to_string(dir.join("global.css")).unwrap(),
+            ".z-scss{}.base-reset-scss{}.base-typography-scss{}.a-scss{}.b-scss{}"
+        );
+    }
+
+    #[test]
+    fn source_maps_are_written_next_to_the_output_files() {
+        let fixture = fixture(&[
+            ("Cargo.lock", ""),
+            ("a.scss", ".a {\n  color: red;\n}\n"),
+            ("b.scss", "\n.b {\n  color: blue;\n}\n"),
+        ]);
+        let dir = fixture.path().canonicalize().unwrap();
+
+        let output_paths = FileOutput {
+            global_css_file_path: Some(dir.join("out/global.<hash>.css")),
+            separate_css_files_path: Some(dir.join("out/separate")),
+            ..Default::default()
+        };
+        let output = |name: &str, source_maps: bool| {
+            let settings = crate::Settings::builder()
+                .class_name_template("<original_name>")
+                .source_maps(source_maps)
+                .build()
+                .unwrap();
+            let compiled_style_sheet = crate::Compiler::new(settings, &dir)
+                .compile(StyleSheetKind::File(dir.join(name)))
+                .unwrap();
+            perform_css_file_output(
+                output_paths.clone(),
+                &compiled_style_sheet,
+                None,
+                &StyleSheetOptions::default(),
+                &dir,
+            )
+            .unwrap();
+        };
+        let global_file = || {
+            let global_manifest: serde_json::Value = serde_json::from_str(
+                &std::fs::read_to_string(dir.join("out/turf-global-manifest.json")).unwrap(),
+            )
+            .unwrap();
+            dir.join("out")
+                .join(global_manifest[0]["file"].as_str().unwrap())
+        };
+
+        output("a.scss", true);
+        output("b.scss", true);
+
+        let global_path = global_file();
+        let global_file_name = global_path.file_name().unwrap().to_str().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&global_path).unwrap(),
+            format!(
+                ".a{{color:red}}\n.b{{color:#00f}}\n/*# sourceMappingURL={global_file_name}.map */"
+            )
+        );
+        let global_source_map: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(dir.join(format!("out/{global_file_name}.map"))).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            global_source_map["sources"],
+            serde_json::json!(["a.scss.css", "b.scss.css"])
+        );
+
+        assert_eq!(
+            std::fs::read_to_string(dir.join("out/separate/a.css")).unwrap(),
+            ".a{color:red}\n/*# sourceMappingURL=a.css.map */"
+        );
+        assert!(dir.join("out/separate/a.css.map").exists());
+
+        // the source maps of an earlier output are removed
+        output("a.scss", false);
+        output("b.scss", false);
+
+        assert!(!global_path.exists());
+        assert!(!dir.join(format!("out/{global_file_name}.map")).exists());
+        assert_eq!(
+            std::fs::read_to_string(global_file()).unwrap(),
+            ".a{color:red}.b{color:#00f}"
+        );
+        assert!(!dir.join("out/separate/a.css.map").exists());
+    }
+
+    #[test]
+    fn output_lock_is_released_on_drop_and_stale_locks_are_taken_over() {
+        let fixture = fixture(&[]);
+        let lock_path = fixture.path().join(".lock");
+
+        let lock = OutputLock::acquire(lock_path.clone()).unwrap();
+        assert!(lock_path.exists());
+        drop(lock);
+        assert!(!lock_path.exists());
+
+        let stale_lock = std::fs::File::create(&lock_path).unwrap();
+        stale_lock
+            .set_modified(std::time::SystemTime::now() - 2 * OutputLock::STALE_AFTER)
+            .unwrap();
+        drop(stale_lock);
+        drop(OutputLock::acquire(lock_path.clone()).unwrap());
+        assert!(!lock_path.exists());
+    }
+
+    #[test]
+    fn media_file_paths_contain_the_media_name_in_front_of_the_extension() {
+        assert_eq!(
+            media_file_path(Path::new("dist/global.css"), "print"),
+            PathBuf::from("dist/global.print.css")
+        );
+        assert_eq!(
+            media_file_path(Path::new("dist/app.<hash>.css"), "print"),
+            PathBuf::from("dist/app.<hash>.print.css")
+        );
+    }
+}
@@ -1,22 +1,51 @@
 //! You're probably looking for `turf` instead.
-
+//!
+//! To compile style sheets programmatically without reading the Cargo manifest, use the [`Compiler`] together with [`Settings::builder`].
+
+mod assets;
+mod bundling;
+mod cache;
+mod compiler;
+mod compression;
+mod crate_imports;
 mod css_compilation;
+mod embedding;
 mod file_output;
 mod hashing;
 mod manifest;
+mod media_split;
+mod package_imports;
 mod path_utils;
 mod settings;
+mod source_maps;
+#[cfg(test)]
+mod test_utils;
 mod transformer;
 
-use std::{collections::HashMap, path::PathBuf, sync::Mutex};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
-pub use settings::Settings;
+pub use assets::Asset;
+pub use compiler::Compiler;
+pub use compression::Compression;
+pub use embedding::{csp_hash, integrity_hash};
+pub use file_output::{write_combined_css_file, write_css_file, CssFileWriteError};
+pub use settings::{Settings, SettingsBuilder, SettingsError};
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error(transparent)]
     CssCompilation(#[from] css_compilation::CssCompilationError),
     #[error(transparent)]
+    Bundling(#[from] bundling::BundlingError),
+    #[error(transparent)]
+    Asset(#[from] assets::AssetError),
+    #[error("error splitting the output by media - {0}")]
+    MediaSplit(#[from] media_split::MediaSplitError),
+    #[error(transparent)]
     Hashing(#[from] hashing::StyleSheetHashingError),
     #[error("error transforming css - {0}")]
     CssTransformation(#[from] transformer::TransformationError),
@@ -24,9 +53,17 @@ pub enum Error {
     NoInputFile,
     #[error(transparent)]
     PathResolution(#[from] path_utils::PathResolutionError),
+    #[error(transparent)]
+    Manifest(#[from] manifest::ManifestError),
+    #[error(transparent)]
+    Cache(#[from] cache::CacheError),
 
     #[error(transparent)]
     CssFileWrite(#[from] file_output::CssFileWriteError),
+    #[error(
+        "unknown bundle '{0}', bundles are configured with the `bundles` of the `file_output`"
+    )]
+    UnknownBundle(String),
     #[error(transparent)]
     Settings(#[from] settings::SettingsError),
 }
@@ -35,87 +72,152 @@ fn compile_message(message: &str) {
     println!("🌱 turf [INFO]: {message}");
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum StyleSheetKind {
     File(PathBuf),
     Inline(String),
 }
 
-#[derive(Debug)]
-pub struct CompiledStyleSheet {
-    pub css: String,
-    pub class_names: HashMap<String, String>,
-    pub original_style_sheet: StyleSheetKind,
+/// The syntax a style sheet is written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Syntax {
+    Scss,
+    /// The indented Sass syntax
+    Sass,
+    /// Plain CSS, which is not compiled by grass and only transformed
+    Css,
 }
 
-fn style_sheet_with_compile_options(
-    style_sheet_input: StyleSheetKind,
-    settings: Settings,
-) -> Result<CompiledStyleSheet, crate::Error> {
-    let hash = hashing::hash_style_sheet(&style_sheet_input)?;
-    let css = css_compilation::compile_style_sheet(&style_sheet_input, &settings)?;
+impl Syntax {
+    /// Picks the syntax from the file extension, files without a `.sass` or `.css` extension are treated as SCSS
+    pub fn for_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("sass") => Self::Sass,
+            Some("css") => Self::Css,
+            _ => Self::Scss,
+        }
+    }
 
-    let (style_sheet_css, class_names) =
-        transformer::transform_stylesheet(&css, &hash, settings.clone())?;
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Scss => "scss",
+            Self::Sass => "sass",
+            Self::Css => "css",
+        }
+    }
+}
 
-    if let Some(file_output) = settings.file_output {
-        file_output::perform_css_file_output(file_output, &style_sheet_css, &style_sheet_input)?;
+#[derive(Debug, thiserror::Error)]
+#[error("unknown style sheet syntax '{0}', expected 'scss', 'sass' or 'css'")]
+pub struct UnknownSyntaxError(String);
+
+impl FromStr for Syntax {
+    type Err = UnknownSyntaxError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "scss" => Ok(Self::Scss),
+            "sass" => Ok(Self::Sass),
+            "css" => Ok(Self::Css),
+            _ => Err(UnknownSyntaxError(value.to_owned())),
+        }
     }
+}
 
-    Ok(CompiledStyleSheet {
-        css: style_sheet_css,
-        class_names,
-        original_style_sheet: style_sheet_input,
-    })
+/// Options of a single style sheet, overriding what is derived from the style sheet itself
+#[derive(Debug, Clone, Default)]
+pub struct StyleSheetOptions {
+    /// Defaults to the syntax matching the file extension, or SCSS for inline style sheets
+    pub syntax: Option<Syntax>,
+    /// The bundle of the file output the CSS is written to instead of the global CSS file
+    pub bundle: Option<String>,
+    /// Overrides the priority from the `order` of the file output, style sheets with a lower priority come first
+    pub priority: Option<i32>,
 }
 
-pub fn style_sheet(style_sheet: StyleSheetKind) -> Result<CompiledStyleSheet, crate::Error> {
-    let settings = Settings::get()?;
+#[derive(Debug)]
+pub struct CompiledStyleSheet {
+    pub css: String,
+    pub hash: String,
+    pub class_names: HashMap<String, String>,
+    pub original_style_sheet: StyleSheetKind,
+    /// The canonicalized paths of the style sheet file and every file it imported
+    pub dependencies: Vec<PathBuf>,
+    /// The assets referenced with `url()` that were not inlined and have to be served under their content-hashed file names
+    pub assets: Vec<Asset>,
+    /// The CSS compressed with the `compress` formats of the file output, only filled if `embed_compressed` is enabled
+    pub compressed: Vec<(Compression, Vec<u8>)>,
+    /// The source map of the CSS as JSON, only generated if `source_maps` is enabled
+    pub source_map: Option<String>,
+}
 
-    let style_sheet = match style_sheet {
-        StyleSheetKind::File(path) => {
-            if path == PathBuf::from("") {
-                return Err(crate::Error::NoInputFile);
-            };
-            let canonicalized_path = path_utils::canonicalize(path)?;
-            StyleSheetKind::File(canonicalized_path)
+impl CompiledStyleSheet {
+    /// The CSS for embedding, followed by its source map as data URI if there is one
+    pub fn css_with_inline_source_map(&self) -> String {
+        match self.source_map {
+            Some(ref source_map) => source_maps::with_inline_source_map(&self.css, source_map),
+            None => self.css.clone(),
         }
-        StyleSheetKind::Inline(inline_style_sheet) => StyleSheetKind::Inline(inline_style_sheet),
-    };
+    }
+}
 
-    style_sheet_with_compile_options(style_sheet, settings)
+pub fn style_sheet(style_sheet: StyleSheetKind) -> Result<CompiledStyleSheet, crate::Error> {
+    style_sheet_with_options(style_sheet, &StyleSheetOptions::default())
 }
 
-static LOAD_PATHS_TRACKED: Mutex<bool> = Mutex::new(false);
+pub fn style_sheet_with_options(
+    style_sheet: StyleSheetKind,
+    options: &StyleSheetOptions,
+) -> Result<CompiledStyleSheet, crate::Error> {
+    let settings = Settings::get()?;
 
-#[derive(Debug, thiserror::Error)]
-pub enum LoadPathTrackingError {
-    #[error("Could not read internal state")]
-    Mutex,
-    #[error(transparent)]
-    Settings(#[from] settings::SettingsError),
-    #[error(transparent)]
-    PathResolution(#[from] path_utils::PathResolutionError),
-}
+    if let Some(ref bundle) = options.bundle {
+        let is_configured = settings
+            .file_output
+            .as_ref()
+            .is_some_and(|file_output| file_output.bundles.contains_key(bundle));
+        if !is_configured {
+            return Err(Error::UnknownBundle(bundle.clone()));
+        }
+    }
 
-pub fn get_untracked_load_paths() -> Result<Vec<PathBuf>, LoadPathTrackingError> {
-    let mut load_paths_tracked = match LOAD_PATHS_TRACKED.lock() {
-        Err(_) => return Err(LoadPathTrackingError::Mutex),
-        Ok(val) => val,
-    };
+    let manifest_dir = manifest::manifest_dir()?;
+    let mut compiler = Compiler::new(settings.clone(), &manifest_dir);
 
-    if *load_paths_tracked {
-        Ok(Vec::new())
-    } else {
-        let settings = Settings::get()?;
-        *load_paths_tracked = true;
+    if let Some(cache_dir) = settings.cache_dir(&manifest_dir) {
+        compiler = compiler.with_cache_dir(cache_dir);
+    }
 
-        let mut result = Vec::new();
+    let mut compiled_style_sheet = compiler.compile_with_options(style_sheet, options)?;
 
-        for path in settings.load_paths {
-            result.extend(path_utils::get_file_paths_recusively(path)?);
+    if let Some(file_output) = settings.file_output.clone() {
+        if file_output.embed_compressed {
+            let css = compiled_style_sheet.css_with_inline_source_map();
+            compiled_style_sheet.compressed = file_output
+                .compress
+                .iter()
+                .map(|compression| (*compression, compression.compress(css.as_bytes())))
+                .collect();
         }
 
-        Ok(result)
+        let split_css = (!file_output.media_split.is_empty())
+            .then(|| {
+                media_split::split_media_rules(
+                    &compiled_style_sheet.css,
+                    &file_output.media_split,
+                    &settings,
+                )
+            })
+            .transpose()?;
+
+        file_output::perform_css_file_output(
+            file_output,
+            &compiled_style_sheet,
+            split_css,
+            options,
+            &manifest_dir,
+        )?;
     }
+
+    Ok(compiled_style_sheet)
 }
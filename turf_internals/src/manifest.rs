@@ -1,4 +1,7 @@
-use std::fs::read_to_string;
+use std::{
+    fs::read_to_string,
+    path::{Path, PathBuf},
+};
 
 use serde::Deserialize;
 
@@ -12,8 +15,16 @@ pub enum ManifestError {
     ReadToml(#[from] toml::de::Error),
 }
 
+pub fn manifest_dir() -> Result<PathBuf, ManifestError> {
+    Ok(PathBuf::from(std::env::var("CARGO_MANIFEST_DIR")?))
+}
+
 pub fn cargo_manifest() -> Result<ManifestWithPackage, ManifestError> {
-    let manifest_path = format!("{}/Cargo.toml", std::env::var("CARGO_MANIFEST_DIR")?);
+    cargo_manifest_in(&manifest_dir()?)
+}
+
+pub fn cargo_manifest_in(manifest_dir: &Path) -> Result<ManifestWithPackage, ManifestError> {
+    let manifest_path = manifest_dir.join("Cargo.toml");
     Ok(toml::de::from_str(&read_to_string(manifest_path)?)?)
 }
 
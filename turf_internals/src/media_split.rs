@@ -0,0 +1,133 @@
+use std::collections::BTreeMap;
+
+use lightningcss::{
+    printer::PrinterOptions,
+    rules::{CssRule, CssRuleList},
+    stylesheet::{ParserOptions, StyleSheet},
+    traits::ToCss,
+};
+
+use crate::Settings;
+
+#[derive(Debug, thiserror::Error)]
+pub enum MediaSplitError {
+    #[error("invalid media query '{0}' of the `media_split` - {1}")]
+    Query(String, String),
+    #[error("error parsing css - {0}")]
+    Parse(String),
+    #[error("error printing css - {0}")]
+    Print(String),
+}
+
+/// The compiled CSS with the top-level `@media` blocks of the `media_split` extracted
+#[derive(Debug, Default)]
+pub struct SplitCss {
+    /// The CSS without the extracted blocks
+    pub main: String,
+    /// The rules inside of the extracted blocks by the name of their media
+    pub media: BTreeMap<String, String>,
+}
+
+/// Extracts the top-level `@media` blocks matching a query of the `media_split` from the CSS
+///
+/// Queries are compared after being printed the same way, so formatting differences between the configuration and the CSS don't matter.
+pub fn split_media_rules(
+    css: &str,
+    media_split: &BTreeMap<String, String>,
+    settings: &Settings,
+) -> Result<SplitCss, MediaSplitError> {
+    let printer_options = || PrinterOptions::from(settings.clone());
+
+    let mut names_by_query = BTreeMap::new();
+    for (name, query) in media_split {
+        names_by_query.insert(normalize_query(query, settings)?, name.as_str());
+    }
+
+    let mut style_sheet = StyleSheet::parse(css, settings.parser_options())
+        .map_err(|e| MediaSplitError::Parse(e.to_string()))?;
+
+    let mut media_rules: BTreeMap<&str, Vec<CssRule>> = BTreeMap::new();
+    let mut main_rules = Vec::new();
+    for rule in std::mem::take(&mut style_sheet.rules.0) {
+        let name = match rule {
+            CssRule::Media(ref media_rule) => {
+                let query = media_rule
+                    .query
+                    .to_css_string(printer_options())
+                    .map_err(|e| MediaSplitError::Print(e.to_string()))?;
+                names_by_query.get(query.as_str()).copied()
+            }
+            _ => None,
+        };
+
+        match (name, rule) {
+            (Some(name), CssRule::Media(media_rule)) => media_rules
+                .entry(name)
+                .or_default()
+                .extend(media_rule.rules.0),
+            (_, rule) => main_rules.push(rule),
+        }
+    }
+
+    let print = |rules: Vec<CssRule>| {
+        StyleSheet::new(Vec::new(), CssRuleList(rules), ParserOptions::default())
+            .to_css(printer_options())
+            .map(|result| result.code)
+            .map_err(|e| MediaSplitError::Print(e.to_string()))
+    };
+
+    let mut media = BTreeMap::new();
+    for name in media_split.keys() {
+        let rules = media_rules.remove(name.as_str()).unwrap_or_default();
+        media.insert(name.clone(), print(rules)?);
+    }
+
+    Ok(SplitCss {
+        main: print(main_rules)?,
+        media,
+    })
+}
+
+fn normalize_query(query: &str, settings: &Settings) -> Result<String, MediaSplitError> {
+    let invalid_query = |reason: String| MediaSplitError::Query(query.to_owned(), reason);
+
+    let css = format!("@media {query} {{}}");
+    let style_sheet = StyleSheet::parse(&css, settings.parser_options())
+        .map_err(|e| invalid_query(e.to_string()))?;
+
+    match style_sheet.rules.0.first() {
+        Some(CssRule::Media(media_rule)) if style_sheet.rules.0.len() == 1 => media_rule
+            .query
+            .to_css_string(PrinterOptions::from(settings.clone()))
+            .map_err(|e| invalid_query(e.to_string())),
+        _ => Err(invalid_query(String::from("not a media query list"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::split_media_rules;
+    use crate::Settings;
+
+    #[test]
+    fn top_level_media_blocks_matching_a_query_are_extracted() {
+        let settings = Settings::builder().minify(true).build().unwrap();
+        let media_split = BTreeMap::from([
+            (String::from("print"), String::from("print")),
+            (String::from("wide"), String::from("(min-width:1200px)")),
+        ]);
+
+        let split_css = split_media_rules(
+            ".a{color:red}@media print{.a{color:#000}}@media (min-width: 1200px){.b{width:50%}}@media screen{.c{color:red}}@media print{.d{display:none}}",
+            &media_split,
+            &settings,
+        )
+        .unwrap();
+
+        assert_eq!(split_css.main, ".a{color:red}@media screen{.c{color:red}}");
+        assert_eq!(split_css.media["print"], ".a{color:#000}.d{display:none}");
+        assert_eq!(split_css.media["wide"], ".b{width:50%}");
+    }
+}
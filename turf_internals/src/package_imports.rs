@@ -0,0 +1,210 @@
+use std::path::{Component, Path, PathBuf};
+
+use serde::Deserialize;
+
+pub(crate) static PACKAGE_URL_PREFIX: &str = "~";
+
+/// The suffixes grass appends to an import URL while probing for the imported file
+static PROBED_SUFFIXES: [&str; 6] = [
+    ".import.sass",
+    ".import.scss",
+    ".import.css",
+    ".sass",
+    ".scss",
+    ".css",
+];
+
+#[derive(Debug, thiserror::Error)]
+pub enum PackageImportError {
+    #[error("error reading package manifest '{0}' - {1}")]
+    Read(PathBuf, std::io::Error),
+    #[error("error parsing package manifest '{0}' - {1}")]
+    Parse(PathBuf, serde_json::Error),
+}
+
+#[derive(Deserialize)]
+struct PackageJson {
+    sass: Option<PathBuf>,
+    style: Option<PathBuf>,
+}
+
+/// The real path of an import of a package
+#[derive(Debug)]
+pub struct PackageImport {
+    pub path: PathBuf,
+    /// The `package.json` the entry file was read from, if the package itself was imported
+    pub package_json: Option<PathBuf>,
+}
+
+/// Resolves paths containing a `~<package>` component to the packages in the package directories
+///
+/// Packages are looked up on disk only, a package directory is expected to be laid out like a `node_modules` directory.
+#[derive(Debug)]
+pub struct PackageResolver {
+    package_dirs: Vec<PathBuf>,
+}
+
+impl PackageResolver {
+    pub fn new(package_dirs: Vec<PathBuf>) -> Self {
+        Self { package_dirs }
+    }
+
+    /// Returns the real path of a path containing a package URL, or `None` for any other path
+    pub fn resolve(&self, path: &Path) -> Result<Option<PackageImport>, PackageImportError> {
+        if self.package_dirs.is_empty() {
+            return Ok(None);
+        }
+
+        let Some((package_name, rest)) = split_package_url(path) else {
+            return Ok(None);
+        };
+
+        if rest.as_os_str().is_empty() {
+            return self.resolve_package_entry(&package_name).map(Some);
+        }
+
+        let path = self
+            .package_dirs
+            .iter()
+            .map(|package_dir| package_dir.join(&package_name).join(&rest))
+            .find(|path| path.exists())
+            .unwrap_or_else(|| self.package_dirs[0].join(&package_name).join(&rest));
+
+        Ok(Some(PackageImport {
+            path,
+            package_json: None,
+        }))
+    }
+
+    /// Resolves an import of the package itself to the file in the `sass` or `style` field of its `package.json`
+    fn resolve_package_entry(
+        &self,
+        package_name: &str,
+    ) -> Result<PackageImport, PackageImportError> {
+        for package_dir in &self.package_dirs {
+            if package_dir.join(package_name).is_dir() {
+                if let Some(package_import) = package_entry(&package_dir.join(package_name))? {
+                    return Ok(package_import);
+                }
+            }
+
+            // grass probes `~bootstrap.scss` when importing `~bootstrap`, which matches an entry file with the same extension
+            for suffix in PROBED_SUFFIXES {
+                let Some(probed_package_name) = package_name.strip_suffix(suffix) else {
+                    continue;
+                };
+                let package_path = package_dir.join(probed_package_name);
+                if !package_path.is_dir() {
+                    continue;
+                }
+
+                if let Some(package_import) = package_entry(&package_path)? {
+                    let probed_extension = suffix.rsplit('.').next();
+                    if package_import.path.extension().and_then(|e| e.to_str()) == probed_extension
+                    {
+                        return Ok(package_import);
+                    }
+                }
+            }
+        }
+
+        Ok(PackageImport {
+            path: self.package_dirs[0].join(package_name),
+            package_json: None,
+        })
+    }
+}
+
+fn package_entry(package_path: &Path) -> Result<Option<PackageImport>, PackageImportError> {
+    let package_json_path = package_path.join("package.json");
+    if !package_json_path.is_file() {
+        return Ok(None);
+    }
+
+    let content = std::fs::read(&package_json_path)
+        .map_err(|e| PackageImportError::Read(package_json_path.clone(), e))?;
+    let package_json: PackageJson = serde_json::from_slice(&content)
+        .map_err(|e| PackageImportError::Parse(package_json_path.clone(), e))?;
+
+    Ok(package_json
+        .sass
+        .or(package_json.style)
+        .map(|entry| PackageImport {
+            path: package_path.join(entry),
+            package_json: Some(package_json_path),
+        }))
+}
+
+/// Splits a path at its `~<package>` component, scoped packages span two components
+fn split_package_url(path: &Path) -> Option<(String, PathBuf)> {
+    let mut components = path.components();
+
+    let mut package_name = components
+        .by_ref()
+        .find_map(|component| match component {
+            Component::Normal(name) => name.to_str()?.strip_prefix(PACKAGE_URL_PREFIX),
+            _ => None,
+        })?
+        .to_owned();
+
+    if package_name.starts_with('@') {
+        let name = components.next()?;
+        package_name = format!("{package_name}/{}", name.as_os_str().to_str()?);
+    }
+
+    Some((package_name, components.collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{split_package_url, PackageResolver};
+    use crate::test_utils::fixture;
+
+    #[test]
+    fn split_package_url_from_import_path() {
+        assert_eq!(
+            split_package_url(Path::new("/project/styles/~bootstrap/scss/grid")),
+            Some((String::from("bootstrap"), PathBuf::from("scss/grid")))
+        );
+        assert_eq!(
+            split_package_url(Path::new("/project/styles/~@scope/package.scss")),
+            Some((String::from("@scope/package.scss"), PathBuf::new()))
+        );
+        assert_eq!(split_package_url(Path::new("/project/styles/grid")), None);
+    }
+
+    #[test]
+    fn resolve_package_entry_from_package_json() {
+        let fixture = fixture(&[
+            (
+                "bootstrap/package.json",
+                r#"{ "style": "dist/bootstrap.css", "sass": "scss/bootstrap.scss" }"#,
+            ),
+            ("bootstrap/scss/bootstrap.scss", ""),
+        ]);
+        let dir = fixture.path();
+
+        let resolver = PackageResolver::new(vec![dir.to_path_buf()]);
+        let resolve = |path: &str| resolver.resolve(Path::new(path)).unwrap().unwrap();
+
+        let package_import = resolve("/project/~bootstrap.scss");
+        assert_eq!(
+            package_import.path,
+            dir.join("bootstrap/scss/bootstrap.scss")
+        );
+        assert_eq!(
+            package_import.package_json,
+            Some(dir.join("bootstrap/package.json"))
+        );
+        assert_ne!(
+            resolve("/project/~bootstrap.sass").path,
+            dir.join("bootstrap/scss/bootstrap.scss")
+        );
+        assert_eq!(
+            resolve("/project/~bootstrap/scss/_grid.scss").path,
+            dir.join("bootstrap/scss/_grid.scss")
+        );
+    }
+}
@@ -1,4 +1,9 @@
-use std::path::{Path, PathBuf};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use crate::settings::LoadPathFiles;
 
 #[derive(thiserror::Error, Debug)]
 #[error("error resolving path '{path}' - {source}")]
@@ -16,33 +21,156 @@ impl From<(PathBuf, std::io::Error)> for PathResolutionError {
     }
 }
 
-pub fn canonicalize<P>(path: P) -> Result<PathBuf, PathResolutionError>
+pub fn canonicalize<B, P>(base_dir: B, path: P) -> Result<PathBuf, PathResolutionError>
 where
+    B: AsRef<Path>,
     P: AsRef<Path>,
 {
-    let mut canonicalized_path = PathBuf::from(
-        std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR environment variable"),
-    );
-    canonicalized_path.push(path.as_ref());
+    let canonicalized_path = base_dir.as_ref().join(path.as_ref());
 
     std::fs::canonicalize(canonicalized_path.clone()).map_err(|e| (canonicalized_path, e).into())
 }
 
-pub fn get_file_paths_recusively(path: PathBuf) -> Result<Vec<PathBuf>, PathResolutionError> {
-    use std::fs::read_dir;
+/// The files found while walking the load paths
+///
+/// Entries that could not be read are collected in `errors` instead of aborting the walk.
+#[derive(Debug, Default)]
+pub struct WalkedFiles {
+    pub files: Vec<PathBuf>,
+    pub errors: Vec<PathResolutionError>,
+}
+
+/// Walks a load path for the files watched when hot reloading
+pub fn get_file_paths_recursively(
+    base_dir: &Path,
+    path: &Path,
+    filter: &LoadPathFiles,
+    visited_dirs: &mut HashSet<PathBuf>,
+    walked_files: &mut WalkedFiles,
+) -> Result<(), PathResolutionError> {
+    let root = canonicalize(base_dir, path)?;
+    std::fs::read_dir(&root).map_err(|e| (root.clone(), e))?;
+
+    walk_dir(&root, &root, filter, visited_dirs, walked_files);
+
+    Ok(())
+}
+
+fn walk_dir(
+    root: &Path,
+    dir: &Path,
+    filter: &LoadPathFiles,
+    visited_dirs: &mut HashSet<PathBuf>,
+    walked_files: &mut WalkedFiles,
+) {
+    let canonical_dir = match std::fs::canonicalize(dir) {
+        Ok(canonical_dir) => canonical_dir,
+        Err(e) => return walked_files.errors.push((dir.to_path_buf(), e).into()),
+    };
+
+    // symlinks pointing to an already visited directory would otherwise recurse forever
+    if !visited_dirs.insert(canonical_dir) {
+        return;
+    }
+
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => return walked_files.errors.push((dir.to_path_buf(), e).into()),
+    };
+
+    for entry in entries {
+        let entry_path = match entry {
+            Ok(entry) => entry.path(),
+            Err(e) => {
+                walked_files.errors.push((dir.to_path_buf(), e).into());
+                continue;
+            }
+        };
 
-    let path = canonicalize(path)?;
-    let mut result = Vec::new();
+        let metadata = match std::fs::metadata(&entry_path) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                walked_files.errors.push((entry_path, e).into());
+                continue;
+            }
+        };
 
-    for item in read_dir(path.clone()).map_err(|e| (path.clone(), e))? {
-        let item_path = item.map_err(|e| (path.clone(), e))?.path();
+        let relative_path = entry_path.strip_prefix(root).unwrap_or(&entry_path);
+        if filter.is_ignored(relative_path, metadata.is_dir()) {
+            continue;
+        }
 
-        if item_path.is_file() {
-            result.push(canonicalize(item_path)?);
-        } else if item_path.is_dir() {
-            result.extend(get_file_paths_recusively(item_path)?);
+        if metadata.is_dir() {
+            walk_dir(root, &entry_path, filter, visited_dirs, walked_files);
+        } else if metadata.is_file() && filter.has_watched_extension(&entry_path) {
+            match std::fs::canonicalize(&entry_path) {
+                Ok(file_path) => walked_files.files.push(file_path),
+                Err(e) => walked_files.errors.push((entry_path, e).into()),
+            }
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::{get_file_paths_recursively, WalkedFiles};
+    use crate::{settings::LoadPathFiles, test_utils::fixture};
+
+    fn walk(dir: &std::path::Path, filter: &LoadPathFiles) -> WalkedFiles {
+        let mut walked_files = WalkedFiles::default();
+        get_file_paths_recursively(
+            dir,
+            std::path::Path::new(""),
+            filter,
+            &mut HashSet::new(),
+            &mut walked_files,
+        )
+        .unwrap();
+        walked_files.files.sort();
+        walked_files
+    }
+
+    #[test]
+    fn walk_filters_extensions_and_ignored_paths() {
+        let fixture = fixture(&[
+            ("_colors.scss", ""),
+            ("nested/_layout.sass", ""),
+            ("nested/logo.png", ""),
+            (".DS_Store", ""),
+            ("vendor/_reset.css", ""),
+        ]);
+        let dir = fixture.path();
 
-    Ok(result)
+        let filter = LoadPathFiles::new(
+            LoadPathFiles::default().extensions,
+            vec![String::from("vendor/")],
+        )
+        .unwrap();
+        let walked_files = walk(dir, &filter);
+
+        let dir = dir.canonicalize().unwrap();
+        assert_eq!(
+            walked_files.files,
+            vec![dir.join("_colors.scss"), dir.join("nested/_layout.sass")]
+        );
+        assert!(walked_files.errors.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn walk_detects_symlink_cycles() {
+        let fixture = fixture(&[("nested/_colors.scss", "")]);
+        let dir = fixture.path();
+        std::os::unix::fs::symlink(dir, dir.join("nested/loop")).unwrap();
+        std::os::unix::fs::symlink(dir.join("missing"), dir.join("broken")).unwrap();
+
+        let walked_files = walk(dir, &LoadPathFiles::default());
+
+        let dir = dir.canonicalize().unwrap();
+        assert_eq!(walked_files.files, vec![dir.join("nested/_colors.scss")]);
+        assert_eq!(walked_files.errors.len(), 1);
+        assert_eq!(walked_files.errors[0].path, dir.join("broken"));
+    }
 }
@@ -1,16 +1,114 @@
-use std::path::PathBuf;
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::{Path, PathBuf},
+};
 
 use serde::Deserialize;
 
 use crate::{
+    compression::Compression,
     manifest::ManifestError,
-    path_utils::{canonicalize, PathResolutionError},
+    path_utils::{canonicalize, get_file_paths_recursively, PathResolutionError, WalkedFiles},
 };
 
+/// How the files in the `separate_css_files_path` are named
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SeparateFileNames {
+    /// The directory structure of the style sheets relative to the `Cargo.toml` is mirrored
+    #[default]
+    Mirrored,
+    /// Each file is named after its style sheet and a hash of its content
+    Hashed,
+}
+
 #[derive(Deserialize, Debug, Default, Clone)]
 pub struct FileOutput {
     pub(crate) global_css_file_path: Option<PathBuf>,
+    /// The CSS files of named bundles, which contain the style sheets assigned to them instead of the global CSS file
+    #[serde(default)]
+    pub(crate) bundles: BTreeMap<String, PathBuf>,
     pub(crate) separate_css_files_path: Option<PathBuf>,
+    #[serde(default)]
+    pub(crate) separate_css_file_names: SeparateFileNames,
+    pub(crate) assets_path: Option<PathBuf>,
+    /// Every CSS file is accompanied by a sibling compressed with each of these formats
+    #[serde(default)]
+    pub(crate) compress: Vec<Compression>,
+    /// The CSS compressed with the `compress` formats is passed to the macros to be embedded
+    #[serde(default)]
+    pub(crate) embed_compressed: bool,
+    #[serde(default)]
+    pub(crate) order: OutputOrder,
+    /// Media queries by name, whose top-level `@media` blocks are moved from the global CSS file and the bundles into files of their own
+    #[serde(default)]
+    pub(crate) media_split: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct RawOrderRule {
+    glob: String,
+    priority: i32,
+}
+
+/// Priorities of the style sheets matching glob patterns, style sheets with a lower priority come first in the global CSS file and the bundles
+#[derive(Deserialize, Clone, Default)]
+#[serde(try_from = "Vec<RawOrderRule>")]
+pub struct OutputOrder {
+    rules: Vec<(String, globset::GlobMatcher, i32)>,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("output order pattern invalid - {0}")]
+pub struct OutputOrderPatternError(#[from] globset::Error);
+
+impl TryFrom<Vec<RawOrderRule>> for OutputOrder {
+    type Error = OutputOrderPatternError;
+
+    fn try_from(raw_rules: Vec<RawOrderRule>) -> Result<Self, Self::Error> {
+        let rules = raw_rules
+            .into_iter()
+            .map(|rule| {
+                let matcher = globset::Glob::new(&rule.glob)?.compile_matcher();
+                Ok((rule.glob, matcher, rule.priority))
+            })
+            .collect::<Result<_, globset::Error>>()?;
+
+        Ok(Self { rules })
+    }
+}
+
+impl OutputOrder {
+    /// The priority of the first rule matching the style sheet, which is given by its path relative to the `Cargo.toml`
+    pub(crate) fn priority(&self, style_sheet: &Path) -> Option<i32> {
+        self.rules
+            .iter()
+            .find(|(_, matcher, _)| matcher.is_match(style_sheet))
+            .map(|(_, _, priority)| *priority)
+    }
+}
+
+impl std::fmt::Debug for OutputOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map()
+            .entries(
+                self.rules
+                    .iter()
+                    .map(|(pattern, _, priority)| (pattern, priority)),
+            )
+            .finish()
+    }
+}
+
+/// Rewriting of the `url()` references to local files
+#[derive(Deserialize, Debug, Default, Clone)]
+pub struct AssetSettings {
+    /// Files smaller than this number of bytes are inlined as data URIs
+    #[serde(default)]
+    pub(crate) inline_limit: u64,
+    /// Prepended to the content-hashed file names of assets that are not inlined
+    #[serde(default)]
+    pub(crate) url_prefix: String,
 }
 
 pub(crate) static DEFAULT_CLASS_NAME_TEMPLATE: &str =
@@ -32,6 +130,284 @@ impl Default for ClassNameGeneration {
     }
 }
 
+pub(crate) static DEFAULT_CACHE_ENABLED: bool = true;
+
+fn default_cache_enabled() -> bool {
+    DEFAULT_CACHE_ENABLED
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CacheSettings {
+    #[serde(default = "default_cache_enabled")]
+    pub(crate) enabled: bool,
+    pub(crate) directory: Option<PathBuf>,
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        Self {
+            enabled: DEFAULT_CACHE_ENABLED,
+            directory: None,
+        }
+    }
+}
+
+pub(crate) static DEFAULT_LOAD_PATH_EXTENSIONS: [&str; 3] = ["scss", "sass", "css"];
+
+fn default_load_path_extensions() -> Vec<String> {
+    DEFAULT_LOAD_PATH_EXTENSIONS.map(String::from).to_vec()
+}
+
+/// The files of the `load_paths` watched when hot reloading
+///
+/// The macros only track the files a style sheet actually imports for rebuilds, so the filter doesn't affect them.
+#[derive(Deserialize, Clone)]
+#[serde(try_from = "RawLoadPathFiles")]
+pub struct LoadPathFiles {
+    pub(crate) extensions: Vec<String>,
+    ignore_patterns: Vec<String>,
+    ignore: ignore::gitignore::Gitignore,
+}
+
+#[derive(Deserialize)]
+struct RawLoadPathFiles {
+    #[serde(default = "default_load_path_extensions")]
+    extensions: Vec<String>,
+    #[serde(default)]
+    ignore: Vec<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("load path ignore pattern invalid - {0}")]
+pub struct IgnorePatternError(#[from] ignore::Error);
+
+impl TryFrom<RawLoadPathFiles> for LoadPathFiles {
+    type Error = IgnorePatternError;
+
+    fn try_from(value: RawLoadPathFiles) -> Result<Self, Self::Error> {
+        Self::new(value.extensions, value.ignore)
+    }
+}
+
+impl LoadPathFiles {
+    pub(crate) fn new(
+        extensions: Vec<String>,
+        ignore_patterns: Vec<String>,
+    ) -> Result<Self, IgnorePatternError> {
+        // patterns are matched against paths relative to the load path they were found in
+        let mut builder = ignore::gitignore::GitignoreBuilder::new("");
+        for pattern in &ignore_patterns {
+            builder.add_line(None, pattern)?;
+        }
+
+        Ok(Self {
+            extensions,
+            ignore: builder.build()?,
+            ignore_patterns,
+        })
+    }
+
+    pub(crate) fn is_ignored(&self, relative_path: &Path, is_dir: bool) -> bool {
+        self.ignore.matched(relative_path, is_dir).is_ignore()
+    }
+
+    pub(crate) fn has_watched_extension(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| self.extensions.iter().any(|watched| watched == extension))
+    }
+}
+
+impl Default for LoadPathFiles {
+    fn default() -> Self {
+        Self::new(default_load_path_extensions(), Vec::new())
+            .expect("empty ignore patterns are valid")
+    }
+}
+
+impl std::fmt::Debug for LoadPathFiles {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LoadPathFiles")
+            .field("extensions", &self.extensions)
+            .field("ignore", &self.ignore_patterns)
+            .finish()
+    }
+}
+
+/// Glob patterns of the files whose classes are kept global
+#[derive(Deserialize, Clone, Default)]
+#[serde(try_from = "Vec<String>")]
+pub struct GlobalSources {
+    patterns: Vec<String>,
+    glob_set: globset::GlobSet,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("global source pattern invalid - {0}")]
+pub struct GlobalSourcePatternError(#[from] globset::Error);
+
+impl TryFrom<Vec<String>> for GlobalSources {
+    type Error = GlobalSourcePatternError;
+
+    fn try_from(patterns: Vec<String>) -> Result<Self, Self::Error> {
+        let mut builder = globset::GlobSetBuilder::new();
+        for pattern in &patterns {
+            builder.add(globset::Glob::new(pattern)?);
+        }
+
+        Ok(Self {
+            glob_set: builder.build()?,
+            patterns,
+        })
+    }
+}
+
+impl GlobalSources {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Matches files inside of the base directory by their relative path, all other files by their absolute path
+    pub(crate) fn is_match(&self, base_dir: &Path, path: &Path) -> bool {
+        let relative_path = base_dir
+            .canonicalize()
+            .ok()
+            .and_then(|base_dir| path.strip_prefix(base_dir).ok().map(Path::to_path_buf));
+
+        self.glob_set
+            .is_match(relative_path.as_deref().unwrap_or(path))
+    }
+}
+
+impl std::fmt::Debug for GlobalSources {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(&self.patterns).finish()
+    }
+}
+
+static DRAFT_NAMES: [(&str, lightningcss::stylesheet::ParserFlags); 3] = [
+    ("nesting", lightningcss::stylesheet::ParserFlags::NESTING),
+    (
+        "custom_media",
+        lightningcss::stylesheet::ParserFlags::CUSTOM_MEDIA,
+    ),
+    (
+        "deep_selector_combinator",
+        lightningcss::stylesheet::ParserFlags::DEEP_SELECTOR_COMBINATOR,
+    ),
+];
+
+/// Draft syntaxes lightningcss parses in addition to the standard syntax
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(try_from = "Vec<String>")]
+pub struct Drafts(pub(crate) lightningcss::stylesheet::ParserFlags);
+
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "unknown draft '{0}', expected one of `nesting`, `custom_media` or `deep_selector_combinator`"
+)]
+pub struct UnknownDraftError(String);
+
+impl TryFrom<Vec<String>> for Drafts {
+    type Error = UnknownDraftError;
+
+    fn try_from(names: Vec<String>) -> Result<Self, Self::Error> {
+        names.into_iter().try_fold(Self::default(), |drafts, name| {
+            DRAFT_NAMES
+                .iter()
+                .find(|(draft_name, _)| *draft_name == name)
+                .map(|(_, flag)| Self(drafts.0 | flag.clone()))
+                .ok_or(UnknownDraftError(name))
+        })
+    }
+}
+
+static FEATURE_NAMES: [(&str, lightningcss::targets::Features); 24] = {
+    use lightningcss::targets::Features;
+
+    [
+        ("nesting", Features::Nesting),
+        ("not_selector_list", Features::NotSelectorList),
+        ("dir_selector", Features::DirSelector),
+        ("lang_selector_list", Features::LangSelectorList),
+        ("is_selector", Features::IsSelector),
+        (
+            "text_decoration_thickness_percent",
+            Features::TextDecorationThicknessPercent,
+        ),
+        ("media_interval_syntax", Features::MediaIntervalSyntax),
+        ("media_range_syntax", Features::MediaRangeSyntax),
+        ("custom_media_queries", Features::CustomMediaQueries),
+        ("clamp_function", Features::ClampFunction),
+        ("color_function", Features::ColorFunction),
+        ("oklab_colors", Features::OklabColors),
+        ("lab_colors", Features::LabColors),
+        ("p3_colors", Features::P3Colors),
+        ("hex_alpha_colors", Features::HexAlphaColors),
+        (
+            "space_separated_color_notation",
+            Features::SpaceSeparatedColorNotation,
+        ),
+        ("font_family_system_ui", Features::FontFamilySystemUi),
+        (
+            "double_position_gradients",
+            Features::DoublePositionGradients,
+        ),
+        ("vendor_prefixes", Features::VendorPrefixes),
+        ("logical_properties", Features::LogicalProperties),
+        ("light_dark", Features::LightDark),
+        ("selectors", Features::Selectors),
+        ("media_queries", Features::MediaQueries),
+        ("colors", Features::Colors),
+    ]
+};
+
+/// Features that are always compiled (`include`) or never compiled (`exclude`), regardless of the browser targets
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(try_from = "RawFeatureFlags")]
+pub struct FeatureFlags {
+    pub(crate) include: lightningcss::targets::Features,
+    pub(crate) exclude: lightningcss::targets::Features,
+}
+
+#[derive(Deserialize)]
+struct RawFeatureFlags {
+    #[serde(default)]
+    include: Vec<String>,
+    #[serde(default)]
+    exclude: Vec<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("unknown feature '{0}', expected the snake case name of a lightningcss feature, e.g. `nesting` or `light_dark`")]
+pub struct UnknownFeatureError(String);
+
+impl TryFrom<RawFeatureFlags> for FeatureFlags {
+    type Error = UnknownFeatureError;
+
+    fn try_from(value: RawFeatureFlags) -> Result<Self, Self::Error> {
+        Ok(Self {
+            include: parse_features(value.include)?,
+            exclude: parse_features(value.exclude)?,
+        })
+    }
+}
+
+fn parse_features(
+    names: Vec<String>,
+) -> Result<lightningcss::targets::Features, UnknownFeatureError> {
+    names.into_iter().try_fold(
+        lightningcss::targets::Features::empty(),
+        |features, name| {
+            FEATURE_NAMES
+                .iter()
+                .find(|(feature_name, _)| *feature_name == name)
+                .map(|(_, feature)| features | *feature)
+                .ok_or(UnknownFeatureError(name))
+        },
+    )
+}
+
 pub(crate) static DEFAULT_MINIFY: bool = true;
 
 fn default_minify() -> bool {
@@ -47,10 +423,27 @@ pub struct Settings {
     #[serde(default)]
     pub(crate) load_paths: Vec<PathBuf>,
     #[serde(default)]
+    pub(crate) package_dirs: Vec<PathBuf>,
+    #[serde(default)]
+    pub(crate) load_path_files: LoadPathFiles,
+    #[serde(default)]
     pub(crate) browser_targets: BrowserTargets,
     #[serde(default)]
+    pub(crate) drafts: Drafts,
+    #[serde(default)]
+    pub(crate) features: FeatureFlags,
+    #[serde(default)]
     pub(crate) class_names: ClassNameGeneration,
+    #[serde(default)]
+    pub(crate) global_sources: GlobalSources,
+    #[serde(default)]
+    pub(crate) bundle_css_imports: bool,
+    #[serde(default)]
+    pub(crate) source_maps: bool,
+    pub(crate) assets: Option<AssetSettings>,
     pub(crate) file_output: Option<FileOutput>,
+    #[serde(default)]
+    pub(crate) cache: CacheSettings,
 }
 
 impl Default for Settings {
@@ -59,39 +452,375 @@ impl Default for Settings {
             debug: false,
             minify: DEFAULT_MINIFY,
             load_paths: Vec::new(),
+            package_dirs: Vec::new(),
+            load_path_files: LoadPathFiles::default(),
             browser_targets: BrowserTargets(None),
+            drafts: Drafts::default(),
+            features: FeatureFlags::default(),
             class_names: ClassNameGeneration::default(),
+            global_sources: GlobalSources::default(),
+            bundle_css_imports: false,
+            source_maps: false,
+            assets: None,
             file_output: None,
+            cache: CacheSettings::default(),
         }
     }
 }
 
 impl Settings {
-    pub fn canonicalized_load_paths(&self) -> Result<Vec<PathBuf>, PathResolutionError> {
+    pub fn with_load_paths<I>(mut self, load_paths: I) -> Self
+    where
+        I: IntoIterator<Item = PathBuf>,
+    {
+        self.load_paths.extend(load_paths);
+        self
+    }
+
+    pub fn canonicalized_load_paths(
+        &self,
+        base_dir: &Path,
+    ) -> Result<Vec<PathBuf>, PathResolutionError> {
         self.load_paths
-            .clone()
-            .into_iter()
-            .map(canonicalize)
+            .iter()
+            .map(|path| canonicalize(base_dir, path))
             .collect()
     }
-}
 
-impl<'a> TryFrom<Settings> for grass::Options<'a> {
-    type Error = PathResolutionError;
+    pub fn canonicalized_package_dirs(
+        &self,
+        base_dir: &Path,
+    ) -> Result<Vec<PathBuf>, PathResolutionError> {
+        self.package_dirs
+            .iter()
+            .map(|path| canonicalize(base_dir, path))
+            .collect()
+    }
+
+    /// Walks the load paths and returns the files matching the `load_path_files` filter
+    ///
+    /// A load path that cannot be read is returned as error, unreadable entries inside of a load path are collected in [`WalkedFiles::errors`].
+    pub fn load_path_files(&self, base_dir: &Path) -> Result<WalkedFiles, PathResolutionError> {
+        let mut walked_files = WalkedFiles::default();
+        let mut visited_dirs = HashSet::new();
+
+        for path in &self.load_paths {
+            get_file_paths_recursively(
+                base_dir,
+                path,
+                &self.load_path_files,
+                &mut visited_dirs,
+                &mut walked_files,
+            )?;
+        }
+
+        walked_files.files.sort();
+        walked_files.files.dedup();
+
+        Ok(walked_files)
+    }
+
+    /// The directory of the compile cache, if caching is enabled
+    ///
+    /// Defaults to a `turf-cache` directory inside the Cargo target directory.
+    pub fn cache_dir(&self, manifest_dir: &Path) -> Option<PathBuf> {
+        if !self.cache.enabled {
+            return None;
+        }
+
+        Some(match self.cache.directory {
+            Some(ref directory) => manifest_dir.join(directory),
+            None => cargo_target_dir(manifest_dir).join("turf-cache"),
+        })
+    }
+
+    /// Whether the `file_output` key is set, which is only used by the macros
+    pub fn has_file_output(&self) -> bool {
+        self.file_output.is_some()
+    }
+
+    pub fn builder() -> SettingsBuilder {
+        SettingsBuilder::default()
+    }
+
+    /// A stable description of the settings that affect the compiled CSS, from which compile cache keys are computed
+    ///
+    /// The debug output, the file output, the cache and the `load_path_files` settings are left out, they don't change the compilation result.
+    pub(crate) fn compile_fingerprint(&self) -> serde_json::Value {
+        let browser_targets = self.browser_targets.0.map(|browsers| {
+            serde_json::json!({
+                "android": browsers.android,
+                "chrome": browsers.chrome,
+                "edge": browsers.edge,
+                "firefox": browsers.firefox,
+                "ie": browsers.ie,
+                "ios_saf": browsers.ios_saf,
+                "opera": browsers.opera,
+                "safari": browsers.safari,
+                "samsung": browsers.samsung,
+            })
+        });
+
+        serde_json::json!({
+            "minify": self.minify,
+            "load_paths": self.load_paths,
+            "package_dirs": self.package_dirs,
+            "browser_targets": browser_targets,
+            "drafts": self.drafts.0.bits(),
+            "features": {
+                "include": self.features.include.bits(),
+                "exclude": self.features.exclude.bits(),
+            },
+            "class_names": {
+                "template": self.class_names.template,
+                "excludes": self.class_names.excludes,
+            },
+            "global_sources": self.global_sources.patterns,
+            "bundle_css_imports": self.bundle_css_imports,
+            "source_maps": self.source_maps,
+            "assets": self.assets.as_ref().map(|assets| serde_json::json!({
+                "inline_limit": assets.inline_limit,
+                "url_prefix": assets.url_prefix,
+            })),
+        })
+    }
+
+    pub(crate) fn parser_options<'o, 'i>(&self) -> lightningcss::stylesheet::ParserOptions<'o, 'i> {
+        lightningcss::stylesheet::ParserOptions {
+            flags: self.drafts.0.clone(),
+            ..Default::default()
+        }
+    }
 
-    fn try_from(val: Settings) -> Result<Self, PathResolutionError> {
+    pub(crate) fn grass_options<'a>(
+        &self,
+        base_dir: &Path,
+    ) -> Result<grass::Options<'a>, PathResolutionError> {
         Ok(grass::Options::default()
             .style(grass::OutputStyle::Expanded)
-            .load_paths(&val.canonicalized_load_paths()?))
+            .load_paths(&self.canonicalized_load_paths(base_dir)?))
+    }
+}
+
+pub(crate) fn cargo_target_dir(manifest_dir: &Path) -> PathBuf {
+    if let Some(target_dir) = std::env::var_os("CARGO_TARGET_DIR") {
+        return PathBuf::from(target_dir);
+    }
+
+    // the target directory of a workspace is located next to its lock file
+    manifest_dir
+        .ancestors()
+        .find(|dir| dir.join("Cargo.lock").is_file())
+        .unwrap_or(manifest_dir)
+        .join("target")
+}
+
+/// Creates [`Settings`] without reading the Cargo manifest
+///
+/// Settings that are not specified keep their default values.
+#[derive(Debug, Default)]
+pub struct SettingsBuilder {
+    settings: Settings,
+    browser_targets: Option<Vec<String>>,
+    load_path_extensions: Option<Vec<String>>,
+    load_path_ignore: Vec<String>,
+    global_sources: Vec<String>,
+    drafts: Vec<String>,
+    included_features: Vec<String>,
+    excluded_features: Vec<String>,
+}
+
+impl SettingsBuilder {
+    pub fn debug(mut self, debug: bool) -> Self {
+        self.settings.debug = debug;
+        self
+    }
+
+    pub fn minify(mut self, minify: bool) -> Self {
+        self.settings.minify = minify;
+        self
+    }
+
+    /// Adds a load path, relative paths are resolved against the base directory of the compiler
+    pub fn load_path<P>(mut self, path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.settings.load_paths.push(path.into());
+        self
+    }
+
+    /// Adds a directory containing packages that are imported with `~<package>`, relative paths are resolved against the base directory of the compiler
+    pub fn package_dir<P>(mut self, path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.settings.package_dirs.push(path.into());
+        self
+    }
+
+    /// Sets the file extensions of the load path files that are watched when hot reloading, defaults to `scss`, `sass` and `css`
+    pub fn load_path_extensions<I, S>(mut self, extensions: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.load_path_extensions = Some(extensions.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Adds a `.gitignore`-style pattern for load path files that are not watched when hot reloading
+    pub fn load_path_ignore<S>(mut self, pattern: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.load_path_ignore.push(pattern.into());
+        self
+    }
+
+    /// Sets the browser targets in [browserslist](https://browsersl.ist/) format
+    pub fn browser_targets<I, S>(mut self, targets: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.browser_targets = Some(targets.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Enables a draft syntax, e.g. `custom_media`
+    pub fn draft<S>(mut self, draft: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.drafts.push(draft.into());
+        self
+    }
+
+    /// Always compiles a feature, e.g. `nesting`, even if all browser targets support it
+    pub fn include_feature<S>(mut self, feature: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.included_features.push(feature.into());
+        self
+    }
+
+    /// Never compiles a feature, e.g. `light_dark`, even if a browser target doesn't support it
+    pub fn exclude_feature<S>(mut self, feature: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.excluded_features.push(feature.into());
+        self
+    }
+
+    pub fn class_name_template<S>(mut self, template: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.settings.class_names.template = template.into();
+        self
+    }
+
+    /// Adds a regex pattern for class names that are excluded from the uniquification process
+    pub fn class_name_exclude<S>(mut self, pattern: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.settings.class_names.excludes.push(pattern.into());
+        self
+    }
+
+    /// Adds a glob pattern for files whose classes are not uniquified, relative patterns are matched against paths relative to the base directory of the compiler
+    pub fn global_source<S>(mut self, pattern: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.global_sources.push(pattern.into());
+        self
+    }
+
+    /// Inlines the CSS files imported with `@import` instead of leaving the imports to the browser
+    pub fn bundle_css_imports(mut self, bundle_css_imports: bool) -> Self {
+        self.settings.bundle_css_imports = bundle_css_imports;
+        self
+    }
+
+    /// Generates source maps of the compiled CSS
+    pub fn source_maps(mut self, source_maps: bool) -> Self {
+        self.settings.source_maps = source_maps;
+        self
+    }
+
+    /// Inlines the assets referenced with `url()` that are smaller than `inline_limit` bytes as data URIs
+    pub fn asset_inline_limit(mut self, inline_limit: u64) -> Self {
+        self.settings
+            .assets
+            .get_or_insert_with(Default::default)
+            .inline_limit = inline_limit;
+        self
+    }
+
+    /// Sets the prefix of the rewritten `url()`s of assets that are not inlined, e.g. `/assets/`
+    pub fn asset_url_prefix<S>(mut self, url_prefix: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.settings
+            .assets
+            .get_or_insert_with(Default::default)
+            .url_prefix = url_prefix.into();
+        self
+    }
+
+    pub fn build(self) -> Result<Settings, SettingsError> {
+        let mut settings = self.settings;
+
+        if let Some(browser_targets) = self.browser_targets {
+            settings.browser_targets = RawBrowserTargets(browser_targets).try_into()?;
+        }
+
+        regex::RegexSet::new(&settings.class_names.excludes)?;
+
+        settings.load_path_files = LoadPathFiles::new(
+            self.load_path_extensions
+                .unwrap_or_else(default_load_path_extensions),
+            self.load_path_ignore,
+        )?;
+        settings.global_sources = self.global_sources.try_into()?;
+        settings.drafts = self.drafts.try_into()?;
+        settings.features = RawFeatureFlags {
+            include: self.included_features,
+            exclude: self.excluded_features,
+        }
+        .try_into()?;
+
+        Ok(settings)
     }
 }
 
 impl<'a> From<Settings> for lightningcss::printer::PrinterOptions<'a> {
     fn from(val: Settings) -> Self {
+        let mut include = val.features.include;
+        // no browser supports custom media queries, so they are always resolved if the draft is enabled
+        if val
+            .drafts
+            .0
+            .contains(lightningcss::stylesheet::ParserFlags::CUSTOM_MEDIA)
+        {
+            include |= lightningcss::targets::Features::CustomMediaQueries;
+        }
+
         lightningcss::printer::PrinterOptions {
             minify: val.minify,
+            source_map: None,
             project_root: None,
-            targets: val.browser_targets.0.into(),
+            targets: lightningcss::targets::Targets {
+                browsers: val.browser_targets.0,
+                include,
+                exclude: val.features.exclude,
+            },
             analyze_dependencies: None,
             pseudo_classes: None,
         }
@@ -156,8 +885,22 @@ static TURF_SETTINGS: std::sync::OnceLock<Settings> = std::sync::OnceLock::new()
 static TURF_DEV_SETTINGS: std::sync::OnceLock<Settings> = std::sync::OnceLock::new();
 
 #[derive(Debug, thiserror::Error)]
-#[error("Could not obtain turf settings from the Cargo manifest")]
-pub struct SettingsError(#[from] ManifestError);
+pub enum SettingsError {
+    #[error("Could not obtain turf settings from the Cargo manifest")]
+    Manifest(#[from] ManifestError),
+    #[error(transparent)]
+    BrowserTargets(#[from] FromRawTargetsErrorCollection),
+    #[error("class name exclude pattern invalid - {0}")]
+    ClassNameExcludes(#[from] regex::Error),
+    #[error(transparent)]
+    LoadPathIgnore(#[from] IgnorePatternError),
+    #[error(transparent)]
+    GlobalSources(#[from] GlobalSourcePatternError),
+    #[error(transparent)]
+    Drafts(#[from] UnknownDraftError),
+    #[error(transparent)]
+    Features(#[from] UnknownFeatureError),
+}
 
 impl Settings {
     pub fn get() -> Result<Self, SettingsError> {
@@ -171,6 +914,23 @@ impl Settings {
         ))
     }
 
+    /// Reads the settings of the current build profile from the Cargo manifest in `manifest_dir`
+    ///
+    /// Unlike [`Settings::get`], the manifest is read on every call.
+    pub fn from_manifest_dir(manifest_dir: &Path) -> Result<Self, SettingsError> {
+        let (dev_settings, prod_settings) = crate::manifest::cargo_manifest_in(manifest_dir)?
+            .package
+            .and_then(|package| package.metadata)
+            .map(|metadata| (metadata.turf_dev, metadata.turf))
+            .unwrap_or_default();
+
+        Ok(Self::choose_settings(
+            dev_settings,
+            prod_settings,
+            cfg!(debug_assertions),
+        ))
+    }
+
     fn choose_settings(
         dev: Option<Settings>,
         prod: Option<Settings>,
@@ -224,6 +984,120 @@ impl Settings {
     }
 }
 
+#[cfg(test)]
+mod builder_tests {
+    use super::{Settings, SettingsError};
+
+    #[test]
+    fn build_settings() {
+        let settings = Settings::builder()
+            .minify(false)
+            .load_path("styles")
+            .browser_targets(["defaults", "safari 12"])
+            .class_name_template("<original_name>-<id>")
+            .class_name_exclude("^global-")
+            .build()
+            .unwrap();
+
+        assert!(!settings.minify);
+        assert_eq!(
+            settings.load_paths,
+            vec![std::path::PathBuf::from("styles")]
+        );
+        assert!(settings.browser_targets.0.is_some());
+        assert_eq!(settings.class_names.template, "<original_name>-<id>");
+        assert_eq!(settings.class_names.excludes, vec!["^global-"]);
+    }
+
+    #[test]
+    fn invalid_browser_targets_are_returned_as_error() {
+        let result = Settings::builder()
+            .browser_targets(["not a browser 12"])
+            .build();
+
+        assert!(matches!(result, Err(SettingsError::BrowserTargets(_))));
+    }
+
+    #[test]
+    fn drafts_and_features_are_parsed_from_their_names() {
+        use lightningcss::{stylesheet::ParserFlags, targets::Features};
+
+        let settings = Settings::builder()
+            .draft("custom_media")
+            .include_feature("nesting")
+            .exclude_feature("colors")
+            .exclude_feature("vendor_prefixes")
+            .build()
+            .unwrap();
+
+        assert_eq!(settings.drafts.0.bits(), ParserFlags::CUSTOM_MEDIA.bits());
+        assert_eq!(settings.features.include.bits(), Features::Nesting.bits());
+        assert_eq!(
+            settings.features.exclude.bits(),
+            (Features::Colors | Features::VendorPrefixes).bits()
+        );
+
+        let result = Settings::builder().draft("custom_selectors").build();
+        assert!(matches!(result, Err(SettingsError::Drafts(_))));
+
+        let result = Settings::builder().include_feature("Nesting").build();
+        assert!(matches!(result, Err(SettingsError::Features(_))));
+    }
+
+    #[test]
+    fn invalid_load_path_ignore_pattern_is_returned_as_error() {
+        let result = Settings::builder().load_path_ignore("[z-a]").build();
+
+        assert!(matches!(result, Err(SettingsError::LoadPathIgnore(_))));
+    }
+
+    #[test]
+    fn compile_fingerprint_only_depends_on_compile_settings() {
+        let settings = Settings::builder().minify(true).build().unwrap();
+        let watching_other_files = Settings::builder()
+            .minify(true)
+            .load_path_extensions(["scss"])
+            .load_path_ignore("vendor/")
+            .build()
+            .unwrap();
+        let with_output = Settings {
+            file_output: Some(super::FileOutput {
+                global_css_file_path: Some(std::path::PathBuf::from("global.css")),
+                ..Default::default()
+            }),
+            cache: super::CacheSettings {
+                enabled: false,
+                directory: None,
+            },
+            ..settings.clone()
+        };
+        let unminified = Settings {
+            minify: false,
+            ..settings.clone()
+        };
+
+        assert_eq!(
+            settings.compile_fingerprint(),
+            with_output.compile_fingerprint()
+        );
+        assert_eq!(
+            settings.compile_fingerprint(),
+            watching_other_files.compile_fingerprint()
+        );
+        assert_ne!(
+            settings.compile_fingerprint(),
+            unminified.compile_fingerprint()
+        );
+    }
+
+    #[test]
+    fn invalid_exclude_pattern_is_returned_as_error() {
+        let result = Settings::builder().class_name_exclude("(").build();
+
+        assert!(matches!(result, Err(SettingsError::ClassNameExcludes(_))));
+    }
+}
+
 #[cfg(test)]
 mod debug_tests {
     use crate::settings::ClassNameGeneration;
@@ -0,0 +1,110 @@
+//! Source maps of the compiled CSS
+
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use parcel_sourcemap::{SourceMap, SourceMapError};
+
+/// The sources are named relative to the base directory, so the maps have no project root of their own
+pub(crate) static PROJECT_ROOT: &str = "/";
+
+/// The comment referencing the source map of the CSS, it is placed on a line of its own at the end of the CSS
+pub(crate) fn source_mapping_url_comment(url: &str) -> String {
+    format!("\n/*# sourceMappingURL={url} */")
+}
+
+/// Appends the source map to the CSS as a base64 data URI
+pub(crate) fn with_inline_source_map(css: &str, source_map: &str) -> String {
+    let data_uri = format!(
+        "data:application/json;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(source_map)
+    );
+    format!("{css}{}", source_mapping_url_comment(&data_uri))
+}
+
+/// The path of the source map of a CSS file, e.g. `style.css.map`
+pub(crate) fn source_map_path(path: &Path) -> PathBuf {
+    let mut source_map_path = path.as_os_str().to_owned();
+    source_map_path.push(".map");
+    PathBuf::from(source_map_path)
+}
+
+/// Concatenates the CSS of several style sheets and combines their source maps into the source map of the result
+///
+/// The CSS of every style sheet starts on a new line, so its mappings only have to be moved by whole lines.
+pub(crate) fn concatenate(parts: &[(&str, &str)]) -> Result<(String, String), SourceMapError> {
+    let mut css = String::new();
+    let mut source_map = SourceMap::new(PROJECT_ROOT);
+    let mut line_offset = 0;
+
+    for (index, (part_css, part_source_map)) in parts.iter().enumerate() {
+        if index > 0 {
+            css.push('\n');
+        }
+        css.push_str(part_css);
+
+        let mut part_source_map = SourceMap::from_json(PROJECT_ROOT, part_source_map)?;
+        source_map.add_sourcemap(&mut part_source_map, line_offset)?;
+        line_offset += part_css.matches('\n').count() as i64 + 1;
+    }
+
+    Ok((css, source_map.to_json(None)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use parcel_sourcemap::SourceMap;
+
+    use super::{concatenate, with_inline_source_map, PROJECT_ROOT};
+    use crate::{transformer::transform_stylesheet, Settings};
+
+    fn compile(css: &str, source_name: &str) -> (String, String) {
+        let settings = Settings::builder()
+            .class_name_template("<original_name>")
+            .build()
+            .unwrap();
+        let (css, _, source_map) = transform_stylesheet(
+            css,
+            "SGVsbG8gdHVyZiB3b3JsZCBvZiBzdHlsZQ",
+            settings,
+            Default::default(),
+            Some(source_name),
+        )
+        .unwrap();
+
+        (css, source_map.unwrap())
+    }
+
+    #[test]
+    fn concatenated_source_maps_point_to_the_source_of_each_line() {
+        let first = compile(".first {\n  color: red;\n}\n", "first.scss.css");
+        let second = compile("\n\n.second {\n  color: blue;\n}\n", "second.scss.css");
+
+        let (css, source_map) =
+            concatenate(&[(&first.0, &first.1), (&second.0, &second.1)]).unwrap();
+        assert_eq!(css, ".first{color:red}\n.second{color:#00f}");
+
+        let mut source_map = SourceMap::from_json(PROJECT_ROOT, &source_map).unwrap();
+        let original = source_map
+            .find_closest_mapping(1, 0)
+            .and_then(|mapping| mapping.original)
+            .unwrap();
+        assert_eq!(
+            source_map.get_source(original.source).unwrap(),
+            "second.scss.css"
+        );
+        assert_eq!(original.original_line, 2);
+        assert_eq!(
+            source_map.get_source_content(original.source).unwrap(),
+            "\n\n.second {\n  color: blue;\n}\n"
+        );
+    }
+
+    #[test]
+    fn inline_source_maps_are_appended_as_data_uri() {
+        assert_eq!(
+            with_inline_source_map(".a{color:red}", "{}"),
+            ".a{color:red}\n/*# sourceMappingURL=data:application/json;base64,e30= */"
+        );
+    }
+}
@@ -0,0 +1,19 @@
+use tempfile::TempDir;
+
+/// Creates a temporary directory containing the given files, which is removed when it is dropped
+///
+/// Parent directories of the files are created as well.
+pub(crate) fn fixture(files: &[(&str, &str)]) -> TempDir {
+    let dir = tempfile::Builder::new()
+        .prefix("turf-test-")
+        .tempdir()
+        .unwrap();
+
+    for (path, content) in files {
+        let path = dir.path().join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    dir
+}
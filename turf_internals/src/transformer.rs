@@ -1,14 +1,31 @@
 use lightningcss::{
+    rules::CssRule,
     selector::{Component, Selector},
-    stylesheet::{ParserOptions, StyleSheet},
+    stylesheet::StyleSheet,
     visit_types,
     visitor::{Visit, VisitTypes, Visitor},
 };
+use parcel_sourcemap::SourceMap;
 use regex::RegexSet;
-use std::{collections::HashMap, convert::Infallible};
+use std::{
+    collections::{HashMap, HashSet},
+    convert::Infallible,
+};
 
 const CHARSET: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ_-";
 
+/// The at-rules enclosing the CSS generated by files matching the `global_sources`
+pub(crate) static GLOBAL_SOURCE_START_RULE: &str = "turf-global-source-start";
+pub(crate) static GLOBAL_SOURCE_END_RULE: &str = "turf-global-source-end";
+
+fn global_source_marker(rule: &CssRule<'_>) -> Option<bool> {
+    match rule {
+        CssRule::Unknown(rule) if *rule.name == *GLOBAL_SOURCE_START_RULE => Some(true),
+        CssRule::Unknown(rule) if *rule.name == *GLOBAL_SOURCE_END_RULE => Some(false),
+        _ => None,
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum TransformationVisitorInitializationError {
     #[error("error obtaining random id - {0}")]
@@ -19,9 +36,13 @@ pub enum TransformationVisitorInitializationError {
 
 pub struct TransformationVisitor {
     pub(crate) classes: HashMap<String, String>,
+    pub(crate) pinned_classes: HashMap<String, String>,
     pub(crate) random_number_generator: oorandom::Rand32,
     pub(crate) class_name_template: String,
     pub(crate) class_name_exclude_patterns: RegexSet,
+    /// The classes of rules between the global source markers, which keep their original names
+    pub(crate) global_classes: HashSet<String>,
+    pub(crate) global_source_depth: usize,
     pub(crate) style_sheet_hash: String,
     pub(crate) debug: bool,
 }
@@ -30,14 +51,25 @@ impl TransformationVisitor {
     fn try_new(
         settings: &crate::Settings,
         style_sheet_hash: &str,
+        pinned_classes: HashMap<String, String>,
     ) -> Result<Self, TransformationVisitorInitializationError> {
         let class_name_generation = settings.class_names.clone();
+        // Only templates with an `<id>` need randomness, which is not available on every target
+        let seed = if class_name_generation.template.contains("<id>") {
+            random_seed()?
+        } else {
+            0
+        };
+
         Ok(Self {
             debug: settings.debug,
             classes: Default::default(),
-            random_number_generator: oorandom::Rand32::new(random_seed()?),
+            pinned_classes,
+            random_number_generator: oorandom::Rand32::new(seed),
             class_name_template: class_name_generation.template,
             class_name_exclude_patterns: RegexSet::new(class_name_generation.excludes)?,
+            global_classes: Default::default(),
+            global_source_depth: 0,
             style_sheet_hash: String::from(style_sheet_hash),
         })
     }
@@ -65,7 +97,11 @@ impl TransformationVisitor {
     }
 
     fn randomized_class_name(&mut self, class_name: String, style_sheet_hash: String) -> String {
-        match self.classes.get(&class_name) {
+        match self
+            .classes
+            .get(&class_name)
+            .or_else(|| self.pinned_classes.get(&class_name))
+        {
             Some(random_class_name) => random_class_name.clone(),
             None => {
                 let id: String = self.randomized_class_id(6);
@@ -84,7 +120,21 @@ impl<'i> Visitor<'i> for TransformationVisitor {
     type Error = Infallible;
 
     fn visit_types(&self) -> VisitTypes {
-        visit_types!(SELECTORS)
+        visit_types!(RULES | SELECTORS)
+    }
+
+    fn visit_rule(&mut self, rule: &mut CssRule<'i>) -> Result<(), Self::Error> {
+        if let Some(is_start) = global_source_marker(rule) {
+            if is_start {
+                self.global_source_depth += 1;
+            } else {
+                self.global_source_depth = self.global_source_depth.saturating_sub(1);
+            }
+            *rule = CssRule::Ignored;
+            return Ok(());
+        }
+
+        rule.visit_children(self)
     }
 
     fn visit_selector(&mut self, selectors: &mut Selector<'i>) -> Result<(), Self::Error> {
@@ -93,7 +143,16 @@ impl<'i> Visitor<'i> for TransformationVisitor {
                 Component::Class(c) => {
                     let original_class_name = c.to_string();
 
-                    if self.class_name_exclude_patterns.is_empty()
+                    if self.global_source_depth > 0 {
+                        self.global_classes.insert(original_class_name.clone());
+
+                        if self.debug {
+                            crate::compile_message(&format!(
+                                "class name of global source kept - {:?}",
+                                &original_class_name
+                            ));
+                        }
+                    } else if self.class_name_exclude_patterns.is_empty()
                         || !self
                             .class_name_exclude_patterns
                             .is_match(&original_class_name)
@@ -168,24 +227,52 @@ pub enum TransformationError {
     Lightningcss(String),
     #[error("Initialization of css tranformer failed")]
     Initialization(#[from] TransformationVisitorInitializationError),
+    #[error("error generating source map - {0}")]
+    SourceMap(String),
 }
 
+/// The transformed CSS, the generated class names by their original names and the source map as JSON
+type TransformedStyleSheet = (String, HashMap<String, String>, Option<String>);
+
+/// Uniquifies the class names and optimizes the CSS, if a source name is given, the source map of the transformed CSS is returned as JSON as well
+///
+/// Every `</style` of the transformed CSS is escaped, so it can be embedded into a `<style>` element. The source map points to the lines of the given CSS, which is embedded in the map as the content of the named source.
 pub fn transform_stylesheet(
     css: &str,
     hash: &str,
     settings: crate::Settings,
-) -> Result<(String, HashMap<String, String>), TransformationError> {
-    let mut stylesheet = StyleSheet::parse(css, ParserOptions::default())
+    pinned_classes: HashMap<String, String>,
+    source_name: Option<&str>,
+) -> Result<TransformedStyleSheet, TransformationError> {
+    let mut stylesheet = StyleSheet::parse(css, settings.parser_options())
         .map_err(|e| e.to_string())
         .map_err(TransformationError::Lightningcss)?;
 
-    let mut visitor = TransformationVisitor::try_new(&settings, hash)?;
+    let mut visitor = TransformationVisitor::try_new(&settings, hash, pinned_classes)?;
 
     stylesheet
         .visit(&mut visitor)
         .expect("css visitor never fails");
 
-    let printer_options: lightningcss::printer::PrinterOptions<'_> = settings.into();
+    // a class of the style sheet's own rules is renamed even if a global source uses the same name
+    let mut classes = visitor.classes;
+    for global_class in visitor.global_classes {
+        classes.entry(global_class.clone()).or_insert(global_class);
+    }
+
+    let mut source_map = source_name
+        .map(|source_name| {
+            let mut source_map = SourceMap::new(crate::source_maps::PROJECT_ROOT);
+            let source_index = source_map.add_source(source_name);
+            source_map
+                .set_source_content(source_index as usize, css)
+                .map(|_| source_map)
+        })
+        .transpose()
+        .map_err(|e| TransformationError::SourceMap(e.to_string()))?;
+
+    let mut printer_options: lightningcss::printer::PrinterOptions<'_> = settings.into();
+    printer_options.source_map = source_map.as_mut();
 
     stylesheet
         .minify(lightningcss::stylesheet::MinifyOptions {
@@ -200,7 +287,28 @@ pub fn transform_stylesheet(
         .map_err(|e| e.to_string())
         .map_err(TransformationError::Lightningcss)?;
 
-    Ok((css_result.code, visitor.classes))
+    let css = crate::embedding::escape_style_end_tags(&css_result.code);
+
+    let source_map = source_map
+        .map(|mut source_map| {
+            // every escape inserts a backslash, the mappings behind it move along with the CSS
+            for index in crate::embedding::style_end_tags(&css_result.code)
+                .into_iter()
+                .rev()
+            {
+                let line_start = css_result.code[..index]
+                    .rfind('\n')
+                    .map_or(0, |newline| newline + 1);
+                let line = css_result.code[..line_start].matches('\n').count();
+                source_map.offset_columns(line as u32, (index - line_start + 1) as u32, 1)?;
+            }
+
+            source_map.to_json(None)
+        })
+        .transpose()
+        .map_err(|e| TransformationError::SourceMap(e.to_string()))?;
+
+    Ok((css, classes, source_map))
 }
 
 fn random_seed() -> Result<u64, getrandom::Error> {
@@ -211,6 +319,8 @@ fn random_seed() -> Result<u64, getrandom::Error> {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
     use crate::settings::{BrowserTargets, ClassNameGeneration};
 
     use super::transform_stylesheet;
@@ -226,6 +336,8 @@ mod tests {
             style,
             "SGVsbG8gdHVyZiB3b3JsZCBvZiBzdHlsZQ",
             crate::Settings::default(),
+            Default::default(),
+            None,
         )
         .unwrap();
 
@@ -248,6 +360,8 @@ mod tests {
             style,
             "SGVsbG8gdHVyZiB3b3JsZCBvZiBzdHlsZQ",
             crate::Settings::default(),
+            Default::default(),
+            None,
         )
         .unwrap();
 
@@ -279,8 +393,14 @@ mod tests {
             class_names: class_name_generation,
             ..Default::default()
         };
-        let transformation_result =
-            transform_stylesheet(style, "SGVsbG8gdHVyZiB3b3JsZCBvZiBzdHlsZQ", settings).unwrap();
+        let transformation_result = transform_stylesheet(
+            style,
+            "SGVsbG8gdHVyZiB3b3JsZCBvZiBzdHlsZQ",
+            settings,
+            Default::default(),
+            None,
+        )
+        .unwrap();
 
         assert!(transformation_result
             .0
@@ -307,8 +427,14 @@ mod tests {
             class_names: class_name_generation,
             ..Default::default()
         };
-        let transformation_result =
-            transform_stylesheet(style, "SGVsbG8gdHVyZiB3b3JsZCBvZiBzdHlsZQ", settings).unwrap();
+        let transformation_result = transform_stylesheet(
+            style,
+            "SGVsbG8gdHVyZiB3b3JsZCBvZiBzdHlsZQ",
+            settings,
+            Default::default(),
+            None,
+        )
+        .unwrap();
 
         assert_eq!(transformation_result.0, ".fancy_style-test{color:red}");
         assert!(transformation_result.0.starts_with(&format!(
@@ -332,8 +458,14 @@ mod tests {
             class_names: class_name_generation,
             ..Default::default()
         };
-        let transformation_result =
-            transform_stylesheet(style, "SGVsbG8gdHVyZiB3b3JsZCBvZiBzdHlsZQ", settings).unwrap();
+        let transformation_result = transform_stylesheet(
+            style,
+            "SGVsbG8gdHVyZiB3b3JsZCBvZiBzdHlsZQ",
+            settings,
+            Default::default(),
+            None,
+        )
+        .unwrap();
 
         assert_eq!(
             transformation_result.0,
@@ -370,8 +502,14 @@ mod tests {
             ),
             ..Default::default()
         };
-        let transformation_result =
-            transform_stylesheet(style, "SGVsbG8gdHVyZiB3b3JsZCBvZiBzdHlsZQ", settings).unwrap();
+        let transformation_result = transform_stylesheet(
+            style,
+            "SGVsbG8gdHVyZiB3b3JsZCBvZiBzdHlsZQ",
+            settings,
+            Default::default(),
+            None,
+        )
+        .unwrap();
 
         assert_eq!(
             transformation_result.0,
@@ -382,4 +520,141 @@ mod tests {
             transformation_result.1.get("test").unwrap()
         )));
     }
+
+    #[test]
+    fn pinned_class_names() {
+        let style = r#"
+            .test {
+                color: red;
+            }
+            .other {
+                color: blue;
+            }
+        "#;
+        let pinned_classes =
+            HashMap::from([(String::from("test"), String::from("pinned-test-class"))]);
+        let transformation_result = transform_stylesheet(
+            style,
+            "SGVsbG8gdHVyZiB3b3JsZCBvZiBzdHlsZQ",
+            crate::Settings::default(),
+            pinned_classes,
+            None,
+        )
+        .unwrap();
+
+        assert!(transformation_result
+            .0
+            .starts_with(".pinned-test-class{color:red}.class-"));
+        assert_eq!(
+            transformation_result.1.get("test").unwrap(),
+            "pinned-test-class"
+        );
+        assert_ne!(
+            transformation_result.1.get("other").unwrap(),
+            "pinned-test-class"
+        );
+    }
+
+    #[test]
+    fn classes_of_global_sources_are_kept() {
+        let style = r#"
+            @turf-global-source-start;
+            .btn {
+                color: red;
+            }
+            @media (min-width: 100px) {
+                .btn-large {
+                    color: blue;
+                }
+            }
+            @turf-global-source-end;
+            .card .btn-large {
+                color: green;
+            }
+        "#;
+        let transformation_result = transform_stylesheet(
+            style,
+            "SGVsbG8gdHVyZiB3b3JsZCBvZiBzdHlsZQ",
+            crate::Settings::default(),
+            Default::default(),
+            None,
+        )
+        .unwrap();
+
+        let card_class_name = transformation_result.1.get("card").unwrap();
+        let btn_large_class_name = transformation_result.1.get("btn-large").unwrap();
+        assert_eq!(
+            transformation_result.0,
+            format!(
+                ".btn{{color:red}}@media (width>=100px){{.btn-large{{color:#00f}}}}.{card_class_name} .{btn_large_class_name}{{color:green}}"
+            )
+        );
+        assert_eq!(transformation_result.1.get("btn").unwrap(), "btn");
+        assert!(card_class_name.starts_with("class-"));
+        assert!(btn_large_class_name.starts_with("class-"));
+    }
+
+    #[test]
+    fn classes_shared_with_a_global_source_are_renamed_outside_of_it() {
+        let style = r#"
+            .btn {
+                color: green;
+            }
+            @turf-global-source-start;
+            .btn {
+                color: red;
+            }
+            @turf-global-source-end;
+            .btn:hover {
+                color: blue;
+            }
+        "#;
+        let class_name_generation = ClassNameGeneration {
+            template: String::from("component-<original_name>"),
+            ..Default::default()
+        };
+        let settings = crate::Settings {
+            class_names: class_name_generation,
+            ..Default::default()
+        };
+        let transformation_result = transform_stylesheet(
+            style,
+            "SGVsbG8gdHVyZiB3b3JsZCBvZiBzdHlsZQ",
+            settings,
+            Default::default(),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(
+            transformation_result.0,
+            ".component-btn{color:green}.btn{color:red}.component-btn:hover{color:#00f}"
+        );
+        assert_eq!(transformation_result.1.get("btn").unwrap(), "component-btn");
+    }
+
+    #[test]
+    fn source_map_mappings_move_along_with_escaped_style_end_tags() {
+        let style = ".a::after {\n  content: \"</style>\";\n}\n\n.b {\n  color: red;\n}\n";
+        let settings = crate::Settings::builder()
+            .class_name_template("<original_name>")
+            .build()
+            .unwrap();
+        let (css, _, source_map) = transform_stylesheet(
+            style,
+            "SGVsbG8gdHVyZiB3b3JsZCBvZiBzdHlsZQ",
+            settings,
+            Default::default(),
+            Some("style.scss.css"),
+        )
+        .unwrap();
+
+        assert_eq!(css, r#".a:after{content:"<\/style>"}.b{color:red}"#);
+        let column = css.find(".b").unwrap() as u32;
+        let mut source_map =
+            parcel_sourcemap::SourceMap::from_json("/", &source_map.unwrap()).unwrap();
+        let mapping = source_map.find_closest_mapping(0, column).unwrap();
+        assert_eq!(mapping.generated_column, column);
+        assert_eq!(mapping.original.unwrap().original_line, 4);
+    }
 }
@@ -0,0 +1,150 @@
+//! Parsing of the `key = "value"` arguments of the macros
+//!
+//! File macros take the arguments after the path, e.g. `style_sheet!("style.css", syntax = "css", bundle = "admin")`.
+//! Inline macros take them in front of the style sheet, terminated by a semicolon, e.g. `inline_style_sheet!(syntax = "css"; .a { color: red; })`.
+
+use std::path::PathBuf;
+
+use proc_macro::{Delimiter, Spacing, TokenStream, TokenTree};
+use turf_internals::{StyleSheetOptions, Syntax};
+
+#[derive(Debug)]
+pub enum ArgumentError {
+    MissingPath,
+    Expected(&'static str, String),
+    UnknownKey(String),
+    Syntax(turf_internals::UnknownSyntaxError),
+    Priority(String),
+}
+
+impl std::fmt::Display for ArgumentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingPath => write!(f, "expected the path of a style sheet file"),
+            Self::Expected(expected, found) => write!(f, "expected {expected}, found `{found}`"),
+            Self::UnknownKey(key) => {
+                write!(
+                    f,
+                    "unknown argument `{key}`, expected `syntax`, `bundle` or `priority`"
+                )
+            }
+            Self::Syntax(error) => write!(f, "{error}"),
+            Self::Priority(value) => write!(f, "expected an integer priority, found `{value}`"),
+        }
+    }
+}
+
+impl std::error::Error for ArgumentError {}
+
+pub fn parse_file_arguments(
+    input: TokenStream,
+) -> Result<(PathBuf, StyleSheetOptions), ArgumentError> {
+    let mut tokens = input.into_iter().peekable();
+
+    let path = match tokens.next() {
+        Some(TokenTree::Literal(literal)) => literal.to_string(),
+        // paths passed through `macro_rules!` are wrapped in an invisible group
+        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::None => {
+            group.stream().to_string()
+        }
+        _ => return Err(ArgumentError::MissingPath),
+    };
+    let path = PathBuf::from(path.trim_matches('"'));
+
+    let mut options = StyleSheetOptions::default();
+
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Punct(punct) if punct.as_char() == ',' => {}
+            token => return Err(ArgumentError::Expected("`,`", token.to_string())),
+        }
+
+        if tokens.peek().is_none() {
+            break;
+        }
+
+        parse_argument(&mut tokens, &mut options)?;
+    }
+
+    Ok((path, options))
+}
+
+/// Returns the inline style sheet without the arguments preceding it
+pub fn parse_inline_arguments(
+    input: TokenStream,
+) -> Result<(String, StyleSheetOptions), ArgumentError> {
+    let mut options = StyleSheetOptions::default();
+
+    if !starts_with_argument(&input) {
+        return Ok((input.to_string(), options));
+    }
+
+    let mut tokens = input.into_iter().peekable();
+
+    loop {
+        parse_argument(&mut tokens, &mut options)?;
+
+        match tokens.next() {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == ',' => {}
+            Some(TokenTree::Punct(punct)) if punct.as_char() == ';' => break,
+            Some(token) => return Err(ArgumentError::Expected("`,` or `;`", token.to_string())),
+            None => return Err(ArgumentError::Expected("`;`", String::new())),
+        }
+    }
+
+    Ok((tokens.collect::<TokenStream>().to_string(), options))
+}
+
+/// Style sheets never start with `<identifier> =`, so arguments can't be confused with the style sheet
+fn starts_with_argument(input: &TokenStream) -> bool {
+    let mut tokens = input.clone().into_iter();
+
+    matches!(
+        (tokens.next(), tokens.next()),
+        (Some(TokenTree::Ident(_)), Some(TokenTree::Punct(punct)))
+            if punct.as_char() == '=' && punct.spacing() == Spacing::Alone
+    )
+}
+
+fn parse_argument<I>(tokens: &mut I, options: &mut StyleSheetOptions) -> Result<(), ArgumentError>
+where
+    I: Iterator<Item = TokenTree>,
+{
+    let key = match tokens.next() {
+        Some(TokenTree::Ident(ident)) => ident.to_string(),
+        token => return Err(expected("an argument name", token)),
+    };
+
+    match tokens.next() {
+        Some(TokenTree::Punct(punct)) if punct.as_char() == '=' => {}
+        token => return Err(expected("`=`", token)),
+    }
+
+    let value = match tokens.next() {
+        Some(TokenTree::Literal(literal)) => literal.to_string().trim_matches('"').to_owned(),
+        // negative numbers are a minus sign followed by a literal
+        Some(TokenTree::Punct(punct)) if punct.as_char() == '-' => match tokens.next() {
+            Some(TokenTree::Literal(literal)) => format!("-{literal}"),
+            token => return Err(expected("a number", token)),
+        },
+        token => return Err(expected("a string value", token)),
+    };
+
+    match key.as_str() {
+        "syntax" => options.syntax = Some(value.parse::<Syntax>().map_err(ArgumentError::Syntax)?),
+        "bundle" => options.bundle = Some(value),
+        "priority" => {
+            options.priority = Some(value.parse().map_err(|_| ArgumentError::Priority(value))?)
+        }
+        _ => return Err(ArgumentError::UnknownKey(key)),
+    }
+
+    Ok(())
+}
+
+fn expected(expected: &'static str, token: Option<TokenTree>) -> ArgumentError {
+    ArgumentError::Expected(
+        expected,
+        token.map(|token| token.to_string()).unwrap_or_default(),
+    )
+}
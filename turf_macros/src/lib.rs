@@ -2,64 +2,85 @@
 
 use convert_case::{Case, Casing};
 use std::{collections::HashMap, path::PathBuf};
-use turf_internals::{CompiledStyleSheet, StyleSheetKind};
+use turf_internals::{CompiledStyleSheet, Compression, StyleSheetKind, StyleSheetOptions};
 
 use proc_macro::TokenStream;
 use quote::quote;
 
+mod arguments;
+
 #[proc_macro]
 pub fn style_sheet(input: TokenStream) -> TokenStream {
-    let input = input.to_string();
-    let sanitized_path = PathBuf::from(input.trim_matches('"'));
+    let (sanitized_path, options) = match arguments::parse_file_arguments(input) {
+        Ok(arguments) => arguments,
+        Err(e) => return to_compile_error(e),
+    };
 
     let ProcessedStyleSheet {
-        untracked_load_paths,
+        dependencies,
         css,
+        hash,
         class_names,
-    } = match handle_style_sheet(StyleSheetKind::File(sanitized_path)) {
+        original_style_sheet,
+        compressed,
+    } = match handle_style_sheet(StyleSheetKind::File(sanitized_path), &options) {
         Ok(result) => result,
-        Err(e) => {
-            return match e {
-                Error::Turf(e) => to_compile_error(e),
-                Error::LoadPathTracking(e) => to_compile_error(e),
-            }
-        }
+        Err(e) => return to_compile_error(e),
     };
 
-    let mut out = quote! {
-        pub static STYLE_SHEET: &'static str = #css;
-    };
+    let mut out = create_style_sheet_static(
+        &css,
+        &hash,
+        &original_style_sheet,
+        &class_names,
+        &dependencies,
+        &options,
+    );
+    out.extend(create_csp_hash_static(&css));
+    out.extend(create_compressed_statics(compressed));
     out.extend(create_classes_structure(class_names));
-    out.extend(create_include_bytes(untracked_load_paths));
+    out.extend(create_include_bytes(dependencies));
 
     out.into()
 }
 
 #[proc_macro]
 pub fn style_sheet_values(input: TokenStream) -> TokenStream {
-    let input = input.to_string();
-    let sanitized_path = PathBuf::from(input.trim_matches('"'));
+    let (sanitized_path, options) = match arguments::parse_file_arguments(input) {
+        Ok(arguments) => arguments,
+        Err(e) => return to_compile_error(e),
+    };
 
     let ProcessedStyleSheet {
-        untracked_load_paths,
+        dependencies,
         css,
+        hash,
         class_names,
-    } = match handle_style_sheet(StyleSheetKind::File(sanitized_path)) {
+        original_style_sheet,
+        compressed,
+    } = match handle_style_sheet(StyleSheetKind::File(sanitized_path), &options) {
         Ok(result) => result,
-        Err(e) => {
-            return match e {
-                Error::Turf(e) => to_compile_error(e),
-                Error::LoadPathTracking(e) => to_compile_error(e),
-            }
-        }
+        Err(e) => return to_compile_error(e),
     };
 
-    let includes = create_include_bytes(untracked_load_paths);
+    let style_sheet = create_style_sheet_static(
+        &css,
+        &hash,
+        &original_style_sheet,
+        &class_names,
+        &dependencies,
+        &options,
+    );
+    let csp_hash = create_csp_hash_static(&css);
+    let includes = create_include_bytes(dependencies);
     let inlines = create_inline_classes_instance(class_names);
+    let values = create_values_tuple(compressed);
     let out = quote! {{
-        pub static STYLE_SHEET: &'static str = #css;
+        #style_sheet
+        #csp_hash
         #includes
         #inlines
+        #values
     }};
 
     out.into()
@@ -67,55 +88,76 @@ pub fn style_sheet_values(input: TokenStream) -> TokenStream {
 
 #[proc_macro]
 pub fn inline_style_sheet(input: TokenStream) -> TokenStream {
-    let input = input.to_string();
+    let (input, options) = match arguments::parse_inline_arguments(input) {
+        Ok(arguments) => arguments,
+        Err(e) => return to_compile_error(e),
+    };
 
     let ProcessedStyleSheet {
-        untracked_load_paths,
+        dependencies,
         css,
+        hash,
         class_names,
-    } = match handle_style_sheet(StyleSheetKind::Inline(input)) {
+        original_style_sheet,
+        compressed,
+    } = match handle_style_sheet(StyleSheetKind::Inline(input), &options) {
         Ok(result) => result,
-        Err(e) => {
-            return match e {
-                Error::Turf(e) => to_compile_error(e),
-                Error::LoadPathTracking(e) => to_compile_error(e),
-            }
-        }
+        Err(e) => return to_compile_error(e),
     };
 
-    let mut out = quote! {
-        pub static STYLE_SHEET: &'static str = #css;
-    };
+    let mut out = create_style_sheet_static(
+        &css,
+        &hash,
+        &original_style_sheet,
+        &class_names,
+        &dependencies,
+        &options,
+    );
+    out.extend(create_csp_hash_static(&css));
+    out.extend(create_compressed_statics(compressed));
     out.extend(create_classes_structure(class_names));
-    out.extend(create_include_bytes(untracked_load_paths));
+    out.extend(create_include_bytes(dependencies));
 
     out.into()
 }
 
 #[proc_macro]
 pub fn inline_style_sheet_values(input: TokenStream) -> TokenStream {
-    let input = input.to_string();
+    let (input, options) = match arguments::parse_inline_arguments(input) {
+        Ok(arguments) => arguments,
+        Err(e) => return to_compile_error(e),
+    };
 
     let ProcessedStyleSheet {
-        untracked_load_paths,
+        dependencies,
         css,
+        hash,
         class_names,
-    } = match handle_style_sheet(StyleSheetKind::Inline(input)) {
+        original_style_sheet,
+        compressed,
+    } = match handle_style_sheet(StyleSheetKind::Inline(input), &options) {
         Ok(result) => result,
-        Err(e) => {
-            return match e {
-                Error::Turf(e) => to_compile_error(e),
-                Error::LoadPathTracking(e) => to_compile_error(e),
-            }
-        }
+        Err(e) => return to_compile_error(e),
     };
 
-    let includes = create_include_bytes(untracked_load_paths);
+    let style_sheet = create_style_sheet_static(
+        &css,
+        &hash,
+        &original_style_sheet,
+        &class_names,
+        &dependencies,
+        &options,
+    );
+    let csp_hash = create_csp_hash_static(&css);
+    let includes = create_include_bytes(dependencies);
     let inlines = create_inline_classes_instance(class_names);
+    let values = create_values_tuple(compressed);
     let out = quote! {{
-        pub static STYLE_SHEET: &'static str = #css;
+        #style_sheet
+        #csp_hash
         #includes
         #inlines
+        #values
     }};
 
     out.into()
@@ -143,6 +185,139 @@ where
     .into()
 }
 
+#[cfg(not(feature = "hot-reload"))]
+fn create_style_sheet_static(
+    css: &str,
+    _hash: &str,
+    _original_style_sheet: &StyleSheetKind,
+    _classes: &HashMap<String, String>,
+    _dependencies: &[PathBuf],
+    _options: &StyleSheetOptions,
+) -> proc_macro2::TokenStream {
+    quote! {
+        pub static STYLE_SHEET: &'static str = #css;
+    }
+}
+
+#[cfg(feature = "hot-reload")]
+fn create_style_sheet_static(
+    css: &str,
+    hash: &str,
+    original_style_sheet: &StyleSheetKind,
+    classes: &HashMap<String, String>,
+    dependencies: &[PathBuf],
+    options: &StyleSheetOptions,
+) -> proc_macro2::TokenStream {
+    let source = match original_style_sheet {
+        StyleSheetKind::File(path) => {
+            let path = debug_only(
+                path.display().to_string(),
+                quote! { &'static str },
+                quote! { "" },
+            );
+            quote! { ::turf::hot_reload::HotStyleSheetSource::File(#path) }
+        }
+        StyleSheetKind::Inline(style_sheet) => {
+            quote! { ::turf::hot_reload::HotStyleSheetSource::Inline(#style_sheet) }
+        }
+    };
+    let manifest_dir = debug_only(
+        std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default(),
+        quote! { &'static str },
+        quote! { "" },
+    );
+    let original_class_names = classes.keys();
+    let randomized_class_names = classes.values();
+    let dependencies = dependencies
+        .iter()
+        .map(|dependency| dependency.display().to_string());
+    let dependencies = debug_only(
+        quote! { &[#(#dependencies,)*] },
+        quote! { &'static [&'static str] },
+        quote! { &[] },
+    );
+    let syntax = match options.syntax {
+        Some(syntax) => {
+            let syntax = syntax.as_str();
+            quote! { ::core::option::Option::Some(#syntax) }
+        }
+        None => quote! { ::core::option::Option::None },
+    };
+
+    quote! {
+        pub static STYLE_SHEET: ::turf::hot_reload::HotStyleSheet =
+            ::turf::hot_reload::HotStyleSheet::new(
+                #css,
+                #hash,
+                #source,
+                #manifest_dir,
+                &[#((#original_class_names, #randomized_class_names),)*],
+                #dependencies,
+                #syntax,
+            );
+    }
+}
+
+/// The value in debug builds, release builds don't embed the absolute paths only needed for hot reloading
+#[cfg(feature = "hot-reload")]
+fn debug_only(
+    value: impl quote::ToTokens,
+    value_type: proc_macro2::TokenStream,
+    release_value: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    quote! {{
+        #[cfg(debug_assertions)]
+        let value: #value_type = #value;
+        #[cfg(not(debug_assertions))]
+        let value: #value_type = #release_value;
+        value
+    }}
+}
+
+#[cfg(not(feature = "hot-reload"))]
+fn style_sheet_reference() -> proc_macro2::TokenStream {
+    quote! { STYLE_SHEET }
+}
+
+#[cfg(feature = "hot-reload")]
+fn style_sheet_reference() -> proc_macro2::TokenStream {
+    quote! { &STYLE_SHEET }
+}
+
+#[cfg(not(feature = "csp"))]
+fn create_csp_hash_static(css: &str) -> proc_macro2::TokenStream {
+    let csp_hash = turf_internals::csp_hash(css);
+
+    quote! {
+        pub static STYLE_SHEET_CSP_HASH: &str = #csp_hash;
+    }
+}
+
+#[cfg(feature = "csp")]
+fn create_csp_hash_static(css: &str) -> proc_macro2::TokenStream {
+    let csp_hash = turf_internals::csp_hash(css);
+
+    quote! {
+        pub static STYLE_SHEET_CSP_HASH: &str = #csp_hash;
+        ::turf::__private::inventory::submit! {
+            ::turf::__private::RegisteredCspHash(STYLE_SHEET_CSP_HASH)
+        }
+    }
+}
+
+fn create_compressed_statics(compressed: Vec<(Compression, Vec<u8>)>) -> proc_macro2::TokenStream {
+    let statics = compressed.into_iter().map(|(compression, content)| {
+        let name = quote::format_ident!("STYLE_SHEET_{}", compression.as_str().to_uppercase());
+        let content = proc_macro2::Literal::byte_string(&content);
+
+        quote! {
+            pub static #name: &[u8] = #content;
+        }
+    });
+
+    quote! { #(#statics)* }
+}
+
 fn create_classes_structure(classes: HashMap<String, String>) -> proc_macro2::TokenStream {
     let original_class_names: Vec<proc_macro2::Ident> = classes
         .keys()
@@ -198,55 +373,83 @@ fn create_inline_classes_instance(classes: HashMap<String, String>) -> proc_macr
                 }
             }
         }
+    }
+}
+
+/// The tuple returned by the `*_values` macros, the compressed CSS is only returned if `embed_compressed` is enabled
+fn create_values_tuple(compressed: Vec<(Compression, Vec<u8>)>) -> proc_macro2::TokenStream {
+    let style_sheet = style_sheet_reference();
+    if compressed.is_empty() {
+        return quote! { (#style_sheet, ClassNames::new()) };
+    }
+
+    let (formats, contents): (Vec<_>, Vec<_>) = compressed
+        .into_iter()
+        .map(|(compression, content)| {
+            (
+                quote::format_ident!("{}", compression.as_str()),
+                proc_macro2::Literal::byte_string(&content),
+            )
+        })
+        .unzip();
+
+    quote! {
+        pub struct CompressedStyleSheets {
+            #(pub #formats: &'static [u8],)*
+        }
 
-        (STYLE_SHEET, ClassNames::new())
+        (
+            #style_sheet,
+            ClassNames::new(),
+            CompressedStyleSheets {
+                #(#formats: #contents,)*
+            },
+        )
     }
 }
 
-fn create_include_bytes(untracked_load_paths: Vec<PathBuf>) -> proc_macro2::TokenStream {
-    let untracked_load_path_values: Vec<String> = untracked_load_paths
+fn create_include_bytes(dependencies: Vec<PathBuf>) -> proc_macro2::TokenStream {
+    let dependency_values: Vec<String> = dependencies
         .into_iter()
         .map(|item| format!("{}", item.as_path().display()))
         .collect();
 
     quote::quote! {
-        #(const _: &[u8] = include_bytes!(#untracked_load_path_values);)*
+        #(const _: &[u8] = include_bytes!(#dependency_values);)*
     }
 }
 
-enum Error {
-    Turf(turf_internals::Error),
-    LoadPathTracking(turf_internals::LoadPathTrackingError),
-}
-
 struct ProcessedStyleSheet {
-    untracked_load_paths: Vec<PathBuf>,
+    dependencies: Vec<PathBuf>,
     css: String,
+    hash: String,
     class_names: HashMap<String, String>,
+    original_style_sheet: StyleSheetKind,
+    compressed: Vec<(Compression, Vec<u8>)>,
 }
 
-fn handle_style_sheet(style_sheet: StyleSheetKind) -> Result<ProcessedStyleSheet, Error> {
+fn handle_style_sheet(
+    style_sheet: StyleSheetKind,
+    options: &StyleSheetOptions,
+) -> Result<ProcessedStyleSheet, turf_internals::Error> {
+    let compiled_style_sheet = turf_internals::style_sheet_with_options(style_sheet, options)?;
+    let css = compiled_style_sheet.css_with_inline_source_map();
     let CompiledStyleSheet {
-        css,
+        hash,
         class_names,
         original_style_sheet,
-    } = turf_internals::style_sheet(style_sheet).map_err(Error::Turf)?;
-
-    let untracked_load_paths = {
-        let mut values =
-            turf_internals::get_untracked_load_paths().map_err(Error::LoadPathTracking)?;
-
-        if let StyleSheetKind::File(current_file_path) = original_style_sheet {
-            values.push(current_file_path);
-        }
-
-        values
-    };
+        dependencies,
+        compressed,
+        ..
+    } = compiled_style_sheet;
 
     Ok(ProcessedStyleSheet {
-        untracked_load_paths,
+        dependencies,
         css,
+        hash,
         class_names,
+        original_style_sheet,
+        compressed,
     })
 }
 